@@ -0,0 +1,431 @@
+// 搜索压缩包里的文件：.tar、.tar.gz / .tgz、.zip
+// 按顺序一次只读出并解压一个普通文件，结果显示为 archive.zip!path/inside.txt
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+use crate::inflate::{crc32, gunzip, inflate};
+
+// 单个文件（以及整个 .tar.gz）解压后的上限，防止压缩炸弹把内存耗尽
+const MAX_INFLATED: usize = 256 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    // 按扩展名判断，不区分大小写
+    pub fn from_path(path: &str) -> Option<ArchiveKind> {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if lower.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// 打开的压缩包，作为迭代器逐个给出其中的普通文件
+// 单个文件出错（超出大小、校验和不对）时给出 Err 后继续；压缩包结构损坏时给出 Err 后结束
+// 错误信息里不带压缩包路径，由调用方加
+pub enum Archive {
+    Tar(TarMembers<Box<dyn Read>>),
+    Zip(ZipMembers<File>),
+}
+
+impl Archive {
+    // bytes_read 累加压缩包在磁盘上的大小
+    pub fn open(path: &str, kind: ArchiveKind, bytes_read: &mut u64) -> io::Result<Archive> {
+        let file = File::open(path)?;
+        *bytes_read += file.metadata()?.len();
+        Ok(match kind {
+            ArchiveKind::Tar => Archive::Tar(TarMembers::new(Box::new(BufReader::new(file)))),
+            // gzip 只能整体解压，结果受同样的上限约束
+            ArchiveKind::TarGz => {
+                let mut bytes = Vec::new();
+                BufReader::new(file).read_to_end(&mut bytes)?;
+                Archive::Tar(TarMembers::new(Box::new(io::Cursor::new(gunzip(&bytes, MAX_INFLATED)?))))
+            }
+            ArchiveKind::Zip => Archive::Zip(ZipMembers::new(file)?),
+        })
+    }
+}
+
+impl Iterator for Archive {
+    type Item = io::Result<Member>;
+
+    fn next(&mut self) -> Option<io::Result<Member>> {
+        match self {
+            Archive::Tar(members) => members.next(),
+            Archive::Zip(members) => members.next(),
+        }
+    }
+}
+
+// 取出 a.zip!dir/b.txt 这种路径里的单个文件，给 TUI 预览用
+pub fn read_member(display_path: &str) -> Option<Vec<u8>> {
+    let (archive, name) = display_path.split_once('!')?;
+    let kind = ArchiveKind::from_path(archive)?;
+    let mut members = Archive::open(archive, kind, &mut 0).ok()?;
+    members.find_map(|member| member.ok().filter(|member| member.name == name)).map(|member| member.data)
+}
+
+// tar 由 512 字节的头和按 512 对齐的数据块组成，两个全零块表示结束
+pub struct TarMembers<R> {
+    reader: R,
+    // GNU 长文件名（L）和 pax 扩展头（x）给出的名字作用于下一个条目
+    long_name: Option<String>,
+    done: bool,
+}
+
+impl<R: Read> TarMembers<R> {
+    pub fn new(reader: R) -> TarMembers<R> {
+        TarMembers { reader, long_name: None, done: false }
+    }
+
+    // 读满 buf，返回实际读到的字节数，只有到达末尾时才会少于 buf 的长度
+    fn fill(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+
+    // 读出一个条目的数据并跳过补齐用的零字节；只按实际读到的字节分配内存，不信任头里的大小
+    fn data(&mut self, size: u64, keep: bool) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut entry = (&mut self.reader).take(size);
+        let got = if keep { entry.read_to_end(&mut data)? as u64 } else { io::copy(&mut entry, &mut io::sink())? };
+        let padding = size.div_ceil(512) * 512 - size;
+        let skipped = io::copy(&mut (&mut self.reader).take(padding), &mut io::sink())?;
+        if got < size || skipped < padding {
+            return Err(invalid("truncated tar entry".to_string()));
+        }
+        Ok(data)
+    }
+
+    fn read_member(&mut self) -> io::Result<Option<Member>> {
+        loop {
+            let mut header = [0u8; 512];
+            if self.fill(&mut header)? < 512 || header.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+            let size = octal(&header[124..136]).ok_or_else(|| invalid("bad tar size".to_string()))?;
+            match header[156] {
+                b'L' => self.long_name = Some(c_string(&self.data(size, true)?)),
+                b'x' => {
+                    let data = self.data(size, true)?;
+                    self.long_name = pax_path(&data).or(self.long_name.take());
+                }
+                // 普通文件；0 是老式 tar 的写法
+                b'0' | 0 => {
+                    let data = self.data(size, true)?;
+                    let name = self.long_name.take().unwrap_or_else(|| {
+                        let name = c_string(&header[0..100]);
+                        let prefix = c_string(&header[345..500]);
+                        if &header[257..262] == b"ustar" && !prefix.is_empty() { format!("{prefix}/{name}") } else { name }
+                    });
+                    return Ok(Some(Member { name, data }));
+                }
+                // 目录、链接等没有内容可搜
+                _ => {
+                    self.data(size, false)?;
+                    self.long_name = None;
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for TarMembers<R> {
+    type Item = io::Result<Member>;
+
+    // tar 没有目录，任何错误之后都找不到下一个头，只能结束
+    fn next(&mut self) -> Option<io::Result<Member>> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_member().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+fn octal(field: &[u8]) -> Option<u64> {
+    let text = c_string(field);
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+// pax 记录格式："长度 key=value\n"
+fn pax_path(data: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(data);
+    text.lines().find_map(|record| {
+        let (_, pair) = record.split_once(' ')?;
+        pair.strip_prefix("path=").map(String::from)
+    })
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> io::Result<usize> {
+    let b = bytes.get(pos..pos + 2).ok_or_else(|| invalid("truncated zip header".to_string()))?;
+    Ok(u16::from_le_bytes([b[0], b[1]]) as usize)
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> io::Result<u32> {
+    let b = bytes.get(pos..pos + 4).ok_or_else(|| invalid("truncated zip header".to_string()))?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+const EOCD: u32 = 0x06054b50;
+const CENTRAL: u32 = 0x02014b50;
+const LOCAL: u32 = 0x04034b50;
+
+// zip 从文件末尾的目录结束记录找到中央目录，再按目录里的偏移逐个读取文件
+// 支持不压缩（0）和 deflate（8）两种方式；加密文件和其他压缩方式跳过，ZIP64 不支持
+// 内存里只放中央目录和当前这一个文件
+pub struct ZipMembers<R> {
+    reader: R,
+    len: u64,
+    central: Vec<u8>,
+    pos: usize,
+    remaining: usize,
+}
+
+struct ZipEntry {
+    name: String,
+    method: usize,
+    crc: u32,
+    compressed: u64,
+    size: usize,
+    local: u64,
+}
+
+impl<R: Read + Seek> ZipMembers<R> {
+    pub fn new(mut reader: R) -> io::Result<ZipMembers<R>> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        // 目录结束记录至少 22 字节，后面可能跟着最长 65535 字节的注释
+        let tail_len = len.min(22 + 65535);
+        let mut tail = vec![0u8; tail_len as usize];
+        reader.seek(SeekFrom::Start(len - tail_len))?;
+        reader.read_exact(&mut tail)?;
+        let eocd = (0..=tail.len().saturating_sub(22))
+            .rev()
+            .find(|&pos| u32_at(&tail, pos).ok() == Some(EOCD))
+            .ok_or_else(|| invalid("not a zip file".to_string()))?;
+        let remaining = u16_at(&tail, eocd + 10)?;
+        let size = u32_at(&tail, eocd + 12)? as u64;
+        let offset = u32_at(&tail, eocd + 16)? as u64;
+        if offset == 0xFFFF_FFFF {
+            return Err(invalid("ZIP64 archives are not supported".to_string()));
+        }
+        if offset + size > len {
+            return Err(invalid("central directory is past the end of the file".to_string()));
+        }
+        let mut central = vec![0u8; size as usize];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut central)?;
+        Ok(ZipMembers { reader, len, central, pos: 0, remaining })
+    }
+
+    // 解析中央目录里的下一项，返回 None 表示这一项不是要搜的普通文件
+    fn entry(&mut self) -> io::Result<Option<ZipEntry>> {
+        let (bytes, pos) = (&self.central[..], self.pos);
+        if u32_at(bytes, pos)? != CENTRAL {
+            return Err(invalid(format!("bad central directory entry at byte {pos}")));
+        }
+        let flags = u16_at(bytes, pos + 8)?;
+        let method = u16_at(bytes, pos + 10)?;
+        let crc = u32_at(bytes, pos + 16)?;
+        let compressed = u32_at(bytes, pos + 20)? as u64;
+        let size = u32_at(bytes, pos + 24)? as usize;
+        let name_len = u16_at(bytes, pos + 28)?;
+        let extra_len = u16_at(bytes, pos + 30)?;
+        let comment_len = u16_at(bytes, pos + 32)?;
+        let local = u32_at(bytes, pos + 42)? as u64;
+        let name_bytes = bytes.get(pos + 46..pos + 46 + name_len).ok_or_else(|| invalid("truncated zip entry".to_string()))?;
+        let name = String::from_utf8_lossy(name_bytes).into_owned();
+        self.pos += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') || flags & 1 != 0 || !matches!(method, 0 | 8) {
+            return Ok(None);
+        }
+        Ok(Some(ZipEntry { name, method, crc, compressed, size, local }))
+    }
+
+    // 读出并解压一个文件，解压结果不能超过中央目录里声明的大小
+    fn read(&mut self, entry: &ZipEntry) -> io::Result<Vec<u8>> {
+        if entry.size > MAX_INFLATED {
+            return Err(invalid(format!("declared size {} is larger than {MAX_INFLATED} bytes", entry.size)));
+        }
+        let mut local = [0u8; 30];
+        self.reader.seek(SeekFrom::Start(entry.local))?;
+        self.reader.read_exact(&mut local)?;
+        if u32_at(&local, 0)? != LOCAL {
+            return Err(invalid("bad local header".to_string()));
+        }
+        let start = entry.local + 30 + u16_at(&local, 26)? as u64 + u16_at(&local, 28)? as u64;
+        if start + entry.compressed > self.len {
+            return Err(invalid("truncated data".to_string()));
+        }
+        let mut raw = vec![0u8; entry.compressed as usize];
+        self.reader.seek(SeekFrom::Start(start))?;
+        self.reader.read_exact(&mut raw)?;
+        let data = if entry.method == 0 { raw } else { inflate(&raw, entry.size)?.0 };
+        if data.len() != entry.size {
+            return Err(invalid(format!("size {} does not match the declared {}", data.len(), entry.size)));
+        }
+        if crc32(&data) != entry.crc {
+            return Err(invalid("checksum mismatch".to_string()));
+        }
+        Ok(data)
+    }
+}
+
+impl<R: Read + Seek> Iterator for ZipMembers<R> {
+    type Item = io::Result<Member>;
+
+    // 单个文件的错误带上文件名后继续下一个；中央目录损坏就没法继续了
+    fn next(&mut self) -> Option<io::Result<Member>> {
+        while self.remaining > 0 {
+            self.remaining -= 1;
+            let entry = match self.entry() {
+                Ok(Some(entry)) => entry,
+                Ok(None) => continue,
+                Err(e) => {
+                    self.remaining = 0;
+                    return Some(Err(e));
+                }
+            };
+            return Some(match self.read(&entry) {
+                Ok(data) => Ok(Member { name: entry.name, data }),
+                Err(e) => Err(invalid(format!("{}: {e}", entry.name))),
+            });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 手工拼一个 ustar 头，校验和字段 tar 读取时不检查，填空格即可
+    fn tar_entry(name: &str, kind: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[148..156].fill(b' ');
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        let mut entry = header;
+        entry.extend_from_slice(data);
+        entry.resize(512 + data.len().div_ceil(512) * 512, 0);
+        entry
+    }
+
+    #[test]
+    fn detects_archives_by_extension() {
+        assert_eq!(Some(ArchiveKind::TarGz), ArchiveKind::from_path("build/out.TAR.GZ"));
+        assert_eq!(Some(ArchiveKind::Zip), ArchiveKind::from_path("a.zip"));
+        assert_eq!(None, ArchiveKind::from_path("notes.txt"));
+    }
+
+    #[test]
+    fn reads_tar_files_and_long_names() {
+        let long = format!("{}/deep.txt", "d".repeat(120));
+        let mut tar = tar_entry("dir/", b'5', b"");
+        tar.extend(tar_entry("dir/a.txt", b'0', b"safe, fast\n"));
+        tar.extend(tar_entry("././@LongLink", b'L', format!("{long}\0").as_bytes()));
+        tar.extend(tar_entry("truncated", b'0', b"productive\n"));
+        tar.extend([0u8; 1024]);
+
+        let members: Vec<Member> = TarMembers::new(&tar[..]).collect::<io::Result<_>>().unwrap();
+        assert_eq!(
+            vec![
+                Member { name: "dir/a.txt".to_string(), data: b"safe, fast\n".to_vec() },
+                Member { name: long, data: b"productive\n".to_vec() },
+            ],
+            members
+        );
+    }
+
+    // zipfile.ZipFile 写出的两个文件：a.txt 不压缩，sub/b.txt 用 deflate
+    const ZIP: [u8; 214] = [
+        0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x52, 0x20, 0x30, 0x3a, 0x36,
+        0x06, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x61, 0x2e, 0x74, 0x78, 0x74, 0x68,
+        0x65, 0x6c, 0x6c, 0x6f, 0x0a, 0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21,
+        0x52, 0xa6, 0xb2, 0x83, 0x2c, 0x06, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x73,
+        0x75, 0x62, 0x2f, 0x62, 0x2e, 0x74, 0x78, 0x74, 0x4b, 0x4c, 0x84, 0x03, 0x2e, 0x00, 0x50, 0x4b, 0x01, 0x02,
+        0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x52, 0x20, 0x30, 0x3a, 0x36, 0x06, 0x00,
+        0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x61, 0x2e, 0x74, 0x78, 0x74, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14,
+        0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x52, 0xa6, 0xb2, 0x83, 0x2c, 0x06, 0x00, 0x00, 0x00, 0x0c,
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, 0x29,
+        0x00, 0x00, 0x00, 0x73, 0x75, 0x62, 0x2f, 0x62, 0x2e, 0x74, 0x78, 0x74, 0x50, 0x4b, 0x05, 0x06, 0x00, 0x00,
+        0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x6a, 0x00, 0x00, 0x00, 0x56, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn reads_stored_and_deflated_zip_entries() {
+        let members: Vec<Member> = ZipMembers::new(io::Cursor::new(&ZIP[..])).unwrap().collect::<io::Result<_>>().unwrap();
+        assert_eq!(
+            vec![
+                Member { name: "a.txt".to_string(), data: b"hello\n".to_vec() },
+                Member { name: "sub/b.txt".to_string(), data: b"aaaaaaaaaaa\n".to_vec() },
+            ],
+            members
+        );
+    }
+
+    #[test]
+    fn reports_members_larger_than_declared_and_keeps_going() {
+        // 把 sub/b.txt 在中央目录里声明的大小从 12 改成 5，解压到第 6 个字节就该停下
+        let mut zip = ZIP;
+        assert_eq!(0x0c, zip[161]);
+        zip[161] = 5;
+        let members: Vec<_> = ZipMembers::new(io::Cursor::new(&zip[..])).unwrap().collect();
+        assert_eq!(2, members.len());
+        assert_eq!(b"hello\n".to_vec(), members[0].as_ref().unwrap().data);
+        assert_eq!("sub/b.txt: decompressed data is larger than 5 bytes", members[1].as_ref().unwrap_err().to_string());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(ZipMembers::new(io::Cursor::new(b"PK but not really a zip")).is_err());
+        let mut tar = TarMembers::new(&[1u8; 512][..]);
+        assert!(tar.next().unwrap().is_err());
+        assert!(tar.next().is_none());
+    }
+}
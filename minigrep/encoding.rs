@@ -0,0 +1,234 @@
+// 输入编码：先看 BOM，没有 BOM 时用 --encoding 指定的编码，默认 UTF-8
+// 非 UTF-8 的输入边读边转成 UTF-8，后面的逐行搜索不用关心原来的编码
+use std::io::{self, BufRead, BufReader, Read};
+
+// GBK 双字节码表：首字节 0x81..=0xFE，尾字节 0x40..=0xFE，每项是小端 u16 的 Unicode 码点，0 表示未定义
+// 由 Python 的 gbk 编解码器生成：
+//   for lead in range(0x81, 0xff):
+//       for trail in range(0x40, 0xff):
+//           try: out += struct.pack('<H', ord(bytes([lead, trail]).decode('gbk')))
+//           except UnicodeDecodeError: out += b'\0\0'
+static GBK_TABLE: &[u8] = include_bytes!("gbk.bin");
+
+const REPLACEMENT: char = char::REPLACEMENT_CHARACTER;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Gbk,
+}
+
+impl Encoding {
+    pub fn from_label(label: &str) -> Option<Encoding> {
+        match label.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Some(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Encoding::Utf16Be),
+            "latin-1" | "latin1" | "iso-8859-1" => Some(Encoding::Latin1),
+            "gbk" | "cp936" | "gb2312" => Some(Encoding::Gbk),
+            _ => None,
+        }
+    }
+
+    // 根据开头的 BOM 判断编码，返回编码和 BOM 的长度
+    pub fn sniff(prefix: &[u8]) -> Option<(Encoding, usize)> {
+        if prefix.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            Some((Encoding::Utf8, 3))
+        } else if prefix.starts_with(&[0xFF, 0xFE]) {
+            Some((Encoding::Utf16Le, 2))
+        } else if prefix.starts_with(&[0xFE, 0xFF]) {
+            Some((Encoding::Utf16Be, 2))
+        } else {
+            None
+        }
+    }
+}
+
+// 包装一个按字节读取的 reader，输出 UTF-8；BOM 优先于 fallback 指定的编码
+pub fn decode<'a, R: BufRead + 'a>(mut reader: R, fallback: Option<Encoding>) -> io::Result<Box<dyn BufRead + 'a>> {
+    let (encoding, bom) = Encoding::sniff(reader.fill_buf()?).unwrap_or((fallback.unwrap_or(Encoding::Utf8), 0));
+    reader.consume(bom);
+    Ok(match encoding {
+        // UTF-8 原样读，非法字节仍然由 lines() 报错
+        Encoding::Utf8 => Box::new(reader),
+        _ => Box::new(BufReader::new(Decoder { inner: reader, encoding, pending: Vec::new(), out: Vec::new(), out_pos: 0 })),
+    })
+}
+
+// 一次性解码整段字节，给索引和预览这类需要整个文件内容的地方用
+pub fn decode_all(bytes: &[u8], fallback: Option<Encoding>) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    decode(bytes, fallback)?.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+struct Decoder<R> {
+    inner: R,
+    encoding: Encoding,
+    // 上一块末尾没凑齐的字节（半个 UTF-16 单元、GBK 的首字节等）
+    pending: Vec<u8>,
+    out: Vec<u8>,
+    out_pos: usize,
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos == self.out.len() {
+            self.out.clear();
+            self.out_pos = 0;
+            let chunk = self.inner.fill_buf()?;
+            if chunk.is_empty() {
+                // 文件结束时还剩不完整的字节，用替换字符表示
+                if self.pending.is_empty() {
+                    return Ok(0);
+                }
+                self.pending.clear();
+                self.out.extend_from_slice(REPLACEMENT.encode_utf8(&mut [0; 4]).as_bytes());
+                break;
+            }
+            self.pending.extend_from_slice(chunk);
+            let len = chunk.len();
+            self.inner.consume(len);
+
+            let mut text = String::new();
+            let used = match self.encoding {
+                Encoding::Utf8 => unreachable!("UTF-8 input is not wrapped in a Decoder"),
+                Encoding::Latin1 => {
+                    text.extend(self.pending.iter().map(|&b| b as char));
+                    self.pending.len()
+                }
+                Encoding::Utf16Le => decode_utf16(&self.pending, u16::from_le_bytes, &mut text),
+                Encoding::Utf16Be => decode_utf16(&self.pending, u16::from_be_bytes, &mut text),
+                Encoding::Gbk => decode_gbk(&self.pending, &mut text),
+            };
+            self.pending.drain(..used);
+            self.out = text.into_bytes();
+        }
+        let n = buf.len().min(self.out.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.out[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
+
+// 返回已经处理掉的字节数，末尾不完整的部分留到下一块
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16, text: &mut String) -> usize {
+    let unit_at = |i: usize| unit([bytes[i], bytes[i + 1]]);
+    let mut i = 0;
+    while i + 2 <= bytes.len() {
+        let high = unit_at(i);
+        if (0xD800..0xDC00).contains(&high) {
+            if i + 4 > bytes.len() {
+                break;
+            }
+            let low = unit_at(i + 2);
+            if (0xDC00..0xE000).contains(&low) {
+                let c = 0x10000 + ((high as u32 - 0xD800) << 10) + (low as u32 - 0xDC00);
+                text.push(char::from_u32(c).unwrap_or(REPLACEMENT));
+                i += 4;
+                continue;
+            }
+        }
+        // 落单的代理项 char::from_u32 会返回 None
+        text.push(char::from_u32(high as u32).unwrap_or(REPLACEMENT));
+        i += 2;
+    }
+    i
+}
+
+fn decode_gbk(bytes: &[u8], text: &mut String) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        let lead = bytes[i];
+        if lead < 0x80 {
+            text.push(lead as char);
+            i += 1;
+            continue;
+        }
+        if !(0x81..=0xFE).contains(&lead) {
+            text.push(REPLACEMENT);
+            i += 1;
+            continue;
+        }
+        let Some(&trail) = bytes.get(i + 1) else {
+            break;
+        };
+        if !(0x40..=0xFE).contains(&trail) || trail == 0x7F {
+            // 尾字节不合法时只吃掉首字节，尾字节可能是正常的 ASCII
+            text.push(REPLACEMENT);
+            i += 1;
+            continue;
+        }
+        let slot = 2 * ((lead as usize - 0x81) * 191 + (trail as usize - 0x40));
+        let code = u16::from_le_bytes([GBK_TABLE[slot], GBK_TABLE[slot + 1]]);
+        text.push(match code {
+            0 => REPLACEMENT,
+            code => char::from_u32(code as u32).unwrap_or(REPLACEMENT),
+        });
+        i += 2;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_str(bytes: &[u8], fallback: Option<Encoding>) -> String {
+        String::from_utf8(decode_all(bytes, fallback).unwrap()).unwrap()
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn sniffs_byte_order_marks() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16le("我啥也不是\r\n😀\n"));
+        assert_eq!("我啥也不是\r\n😀\n", decode_str(&bytes, None));
+
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend("Rust".encode_utf16().flat_map(|u| u.to_be_bytes()));
+        assert_eq!("Rust", decode_str(&bytes, Some(Encoding::Gbk)));
+
+        assert_eq!("plain", decode_str(b"\xEF\xBB\xBFplain", None));
+    }
+
+    #[test]
+    fn explicit_encodings_without_bom() {
+        assert_eq!("café", decode_str(b"caf\xe9", Some(Encoding::Latin1)));
+        assert_eq!("中文 text", decode_str(b"\xd6\xd0\xce\xc4 text", Some(Encoding::Gbk)));
+        assert_eq!("abc", decode_str(&utf16le("abc"), Some(Encoding::Utf16Le)));
+    }
+
+    #[test]
+    fn malformed_input_becomes_replacement_characters() {
+        assert_eq!("\u{FFFD}\u{7f}a\u{FFFD}", decode_str(b"\x81\x7fa\x81", Some(Encoding::Gbk)));
+        assert_eq!("a\u{FFFD}", decode_str(&[b'a', 0, 0x00, 0xD8], Some(Encoding::Utf16Le)));
+    }
+
+    #[test]
+    fn decodes_across_buffer_boundaries() {
+        // BufReader 容量只有 1 字节，每个多字节字符都会被切开
+        let gbk = b"\xd6\xd0\xce\xc4\n";
+        let mut text = String::new();
+        decode(BufReader::with_capacity(1, &gbk[..]), Some(Encoding::Gbk)).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!("中文\n", text);
+
+        let utf16 = utf16le("😀x");
+        let mut text = String::new();
+        decode(BufReader::with_capacity(1, &utf16[..]), Some(Encoding::Utf16Le)).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!("😀x", text);
+    }
+
+    #[test]
+    fn parses_labels() {
+        assert_eq!(Some(Encoding::Utf16Le), Encoding::from_label("UTF-16LE"));
+        assert_eq!(Some(Encoding::Gbk), Encoding::from_label("gbk"));
+        assert_eq!(None, Encoding::from_label("ebcdic"));
+    }
+}
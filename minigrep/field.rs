@@ -0,0 +1,366 @@
+// 结构化字段搜索：只在 JSON Lines 的某个 key 或 CSV 的某一列里匹配 query
+
+#[derive(Debug, PartialEq)]
+pub enum Field {
+    // --field level 或 --field http.status（用 . 访问嵌套对象）
+    Json(Vec<String>),
+    // --column 3，列号从 1 开始
+    Csv(usize),
+}
+
+impl Field {
+    pub fn json(key: &str) -> Field {
+        Field::Json(key.split('.').map(String::from).collect())
+    }
+
+    // 取出这一行里对应字段的文本；解析失败或字段不存在时返回 None
+    pub fn extract(&self, line: &str) -> Option<String> {
+        match self {
+            Field::Json(path) => {
+                let mut value = JsonParser::new(line).parse_document()?;
+                for key in path {
+                    value = match value {
+                        Json::Object(members) => members.into_iter().find(|(k, _)| k == key)?.1,
+                        _ => return None,
+                    };
+                }
+                value.into_text()
+            }
+            Field::Csv(column) => csv_fields(line)?.into_iter().nth(column.checked_sub(1)?),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    // 数字保留原文，匹配时按字面比较
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn into_text(self) -> Option<String> {
+        match self {
+            Json::Null => Some("null".to_string()),
+            Json::Bool(b) => Some(b.to_string()),
+            Json::Number(n) | Json::String(n) => Some(n),
+            Json::Array(_) | Json::Object(_) => None,
+        }
+    }
+}
+
+// 数组和对象最多嵌套这么多层；解析是递归的，一行 "[[[[..." 不能把栈撑爆
+const MAX_DEPTH: usize = 128;
+
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(text: &'a str) -> JsonParser<'a> {
+        JsonParser { text, pos: 0, depth: 0 }
+    }
+
+    fn parse_document(&mut self) -> Option<Json> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        // 值后面不允许再有多余内容
+        (self.pos == self.text.len()).then_some(value)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn expect(&mut self, b: u8) -> Option<()> {
+        (self.next()? == b).then_some(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' | b'[' => {
+                if self.depth == MAX_DEPTH {
+                    return None;
+                }
+                self.depth += 1;
+                let value = if self.peek() == Some(b'{') { self.parse_object() } else { self.parse_array() };
+                self.depth -= 1;
+                value
+            }
+            b'"' => self.parse_string().map(Json::String),
+            b't' => self.parse_literal("true", Json::Bool(true)),
+            b'f' => self.parse_literal("false", Json::Bool(false)),
+            b'n' => self.parse_literal("null", Json::Null),
+            b'-' | b'0'..=b'9' => self.parse_number(),
+            _ => None,
+        }
+    }
+
+    fn parse_literal(&mut self, word: &str, value: Json) -> Option<Json> {
+        if self.text[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<Json> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.next()? {
+            b'0' => {}
+            b'1'..=b'9' => self.skip_digits(),
+            _ => return None,
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.require_digits()?;
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            self.require_digits()?;
+        }
+        Some(Json::Number(self.text[start..self.pos].to_string()))
+    }
+
+    fn skip_digits(&mut self) {
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+    }
+
+    fn require_digits(&mut self) -> Option<()> {
+        let start = self.pos;
+        self.skip_digits();
+        (self.pos > start).then_some(())
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            // 普通字符整段拷贝；遇到 " 或 \ 才需要特殊处理，它们都是 ASCII，切片不会落在多字节字符中间
+            let rest = &self.text[self.pos..];
+            let end = rest.find(['"', '\\'])?;
+            if rest[..end].chars().any(|c| c < ' ') {
+                return None;
+            }
+            out.push_str(&rest[..end]);
+            self.pos += end;
+            match self.next()? {
+                b'"' => return Some(out),
+                _ => out.push(self.parse_escape()?),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Option<char> {
+        Some(match self.next()? {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.parse_hex4()?;
+                if (0xD800..0xDC00).contains(&high) {
+                    // UTF-16 代理对：😀 这样的两段合成一个字符
+                    self.expect(b'\\')?;
+                    self.expect(b'u')?;
+                    let low = self.parse_hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return None;
+                    }
+                    char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))?
+                } else {
+                    char::from_u32(high)?
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    fn parse_hex4(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.pos..self.pos + 4)?;
+        // from_str_radix 会接受前导 +，所以先逐位检查
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn parse_array(&mut self) -> Option<Json> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Some(Json::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => continue,
+                b']' => return Some(Json::Array(items)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Option<Json> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Some(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            members.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => continue,
+                b'}' => return Some(Json::Object(members)),
+                _ => return None,
+            }
+        }
+    }
+}
+
+// 按 RFC 4180 拆分一行 CSV：带引号的字段里可以有逗号，"" 表示一个引号
+// 引号没闭合或闭合引号后面还跟着别的字符时返回 None
+pub fn csv_fields(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => break,
+                    c => field.push(c),
+                }
+            }
+            match chars.next() {
+                Some(',') => {
+                    fields.push(std::mem::take(&mut field));
+                    continue;
+                }
+                None => {
+                    fields.push(field);
+                    return Some(fields);
+                }
+                Some(_) => return None,
+            }
+        }
+        loop {
+            match chars.next() {
+                Some(',') => break,
+                Some(c) => field.push(c),
+                None => {
+                    fields.push(field);
+                    return Some(fields);
+                }
+            }
+        }
+        fields.push(std::mem::take(&mut field));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_top_level_and_nested_keys() {
+        let line = r#"{"level":"error","msg":"disk \"full\"","http":{"status":503},"ok":false}"#;
+        assert_eq!(Some("error".to_string()), Field::json("level").extract(line));
+        assert_eq!(Some("disk \"full\"".to_string()), Field::json("msg").extract(line));
+        assert_eq!(Some("503".to_string()), Field::json("http.status").extract(line));
+        assert_eq!(Some("false".to_string()), Field::json("ok").extract(line));
+        assert_eq!(None, Field::json("missing").extract(line));
+        assert_eq!(None, Field::json("http").extract(line));
+    }
+
+    #[test]
+    fn json_unicode_escapes() {
+        let line = r#"{"msg":"caf\u00e9 \ud83d\ude00 \u4e2d文"}"#;
+        assert_eq!(Some("café 😀 中文".to_string()), Field::json("msg").extract(line));
+        // 落单的代理项不是合法字符
+        assert_eq!(None, Field::json("msg").extract(r#"{"msg":"\ud83d"}"#));
+        assert_eq!(None, Field::json("msg").extract(r#"{"msg":"\ud83d\u0041"}"#));
+        assert_eq!(None, Field::json("msg").extract(r#"{"msg":"\ude00"}"#));
+    }
+
+    #[test]
+    fn json_limits_nesting_depth() {
+        let nested = |depth: usize| format!("{{\"a\":{}1{}}}", "[".repeat(depth), "]".repeat(depth));
+        assert!(JsonParser::new(&nested(MAX_DEPTH - 1)).parse_document().is_some());
+        assert!(JsonParser::new(&nested(MAX_DEPTH)).parse_document().is_none());
+        assert_eq!(None, Field::json("a").extract(&"[".repeat(1_000_000)));
+    }
+
+    #[test]
+    fn json_rejects_malformed_lines() {
+        assert_eq!(None, Field::json("a").extract(r#"{"a":"unterminated}"#));
+        assert_eq!(None, Field::json("a").extract(r#"{"a":1} trailing"#));
+        assert_eq!(None, Field::json("a").extract("level=error"));
+        assert_eq!(None, Field::json("a").extract(r#"{"a":01}"#));
+    }
+
+    #[test]
+    fn csv_quoted_fields() {
+        assert_eq!(
+            Some(vec!["1".to_string(), "Smith, John".to_string(), "say \"hi\"".to_string(), String::new()]),
+            csv_fields(r#"1,"Smith, John","say ""hi""","#)
+        );
+        assert_eq!(Some("Smith, John".to_string()), Field::Csv(2).extract(r#"1,"Smith, John",x"#));
+        assert_eq!(None, Field::Csv(4).extract("a,b,c"));
+        assert_eq!(None, Field::Csv(0).extract("a,b,c"));
+    }
+
+    #[test]
+    fn csv_rejects_broken_quotes() {
+        assert_eq!(None, csv_fields(r#"a,"open"#));
+        assert_eq!(None, csv_fields(r#""closed"junk,b"#));
+    }
+}
@@ -0,0 +1,275 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::encoding::{self, Encoding};
+use crate::Config;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// 每次 poll 最多读这么多字节；一下子追加了很多内容时分几次读完，内存占用有上限
+const MAX_READ: u64 = 1 << 20;
+
+// 类似 tail -f -n 0：从打开时的文件末尾开始，记住已读到的位置，每次只读新追加的内容
+pub struct Follower {
+    path: PathBuf,
+    file: File,
+    pos: u64,
+    id: Option<u64>,
+    // --encoding：每一整行按这个编码转成 UTF-8
+    encoding: Option<Encoding>,
+    // 还没遇到换行符的半行，等下一次追加补全；按字节存，多字节字符被拆在两次追加之间也不会变成乱码
+    pending: Vec<u8>,
+}
+
+impl Follower {
+    pub fn open(path: impl Into<PathBuf>, encoding: Option<Encoding>) -> io::Result<Follower> {
+        let path = path.into();
+        let file = File::open(&path)?;
+        let meta = file.metadata()?;
+        Ok(Follower { path, file, pos: meta.len(), id: file_id(&meta), encoding, pending: Vec::new() })
+    }
+
+    // 返回自上次调用以来新出现的完整行；新内容超过 MAX_READ 时剩下的留给下一次
+    pub fn poll(&mut self) -> io::Result<Vec<String>> {
+        let meta = match fs::metadata(&self.path) {
+            Ok(meta) => meta,
+            // 日志轮转时旧文件已被移走、新文件还没创建，下次再试
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = Vec::new();
+        if file_id(&meta) != self.id {
+            // 轮转：先把旧文件剩下的内容读完，再切换到新文件；剩得多就分几次读
+            if self.read_new(&mut lines)? == MAX_READ {
+                return Ok(lines);
+            }
+            self.flush_pending(&mut lines);
+            self.file = File::open(&self.path)?;
+            self.id = file_id(&self.file.metadata()?);
+            self.pos = 0;
+        } else if meta.len() < self.pos {
+            // 截断：文件被清空或重写，从头开始读
+            self.pos = 0;
+            self.pending.clear();
+        }
+        self.read_new(&mut lines)?;
+        Ok(lines)
+    }
+
+    // 返回这次读到的字节数
+    fn read_new(&mut self, lines: &mut Vec<String>) -> io::Result<u64> {
+        self.file.seek(SeekFrom::Start(self.pos))?;
+        let read = (&mut self.file).take(MAX_READ).read_to_end(&mut self.pending)? as u64;
+        self.pos += read;
+
+        // 只解码已经完整的行，最后的半行留在 pending 里
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = &line[..end];
+            lines.push(self.decode(line.strip_suffix(b"\r").unwrap_or(line)));
+        }
+        Ok(read)
+    }
+
+    fn flush_pending(&mut self, lines: &mut Vec<String>) {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            lines.push(self.decode(&line));
+        }
+    }
+
+    // 非法字节换成 U+FFFD，日志里偶尔一行坏数据不会让跟踪中断
+    fn decode(&self, line: &[u8]) -> String {
+        let decoded = encoding::decode_all(line, self.encoding).unwrap_or_else(|_| line.to_vec());
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+// 正在跟踪的一个文件
+struct Followed<'a> {
+    path: &'a str,
+    follower: Follower,
+    count: usize,
+    // 上一次 poll 的错误，同样的错误不重复报
+    error: Option<String>,
+}
+
+pub fn follow(config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut followed = Vec::new();
+    for path in &config.file_paths {
+        followed.push(Followed { path, follower: Follower::open(path, config.encoding)?, count: 0, error: None });
+    }
+
+    // 会 stat 每个路径，只在开始时算一次
+    let show_file_names = config.show_file_names();
+    let mut total = 0;
+    loop {
+        let mut idle = true;
+        for file in followed.iter_mut() {
+            // 一个文件读出错只报告这个文件，其他文件照常跟踪，它恢复后也接着读
+            let lines = match file.follower.poll() {
+                Ok(lines) => {
+                    file.error = None;
+                    lines
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    if file.error.as_ref() != Some(&error) {
+                        eprintln!("minigrep: {}: {error}", file.path);
+                    }
+                    file.error = Some(error);
+                    continue;
+                }
+            };
+            idle &= lines.is_empty();
+            for line in lines {
+                if !config.is_match(&line) {
+                    continue;
+                }
+                // -m / --max-total 额度用完后这个文件的新行不再输出
+                if config.remaining(file.count, total) == Some(0) {
+                    break;
+                }
+                if show_file_names {
+                    println!("{}:{line}", file.path);
+                } else {
+                    println!("{line}");
+                }
+                file.count += 1;
+                total += 1;
+            }
+        }
+        if followed.iter().all(|file| config.remaining(file.count, total) == Some(0)) {
+            return Ok(());
+        }
+        // 有文件一次没读完时马上再读，不等
+        if idle {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("minigrep-follow-{}-{name}", std::process::id()))
+    }
+
+    fn append(path: &PathBuf, text: &str) {
+        let mut file = OpenOptions::new().append(true).create(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn picks_up_appended_lines() {
+        let path = temp_path("append");
+        fs::write(&path, "old\n").unwrap();
+        let mut follower = Follower::open(&path, None).unwrap();
+        // 打开之前就有的内容不输出
+        assert!(follower.poll().unwrap().is_empty());
+        append(&path, "first\r\nsecond\n");
+        assert_eq!(vec!["first", "second"], follower.poll().unwrap());
+
+        append(&path, "half");
+        assert!(follower.poll().unwrap().is_empty());
+        append(&path, " line\n");
+        assert_eq!(vec!["half line"], follower.poll().unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keeps_characters_split_across_appends() {
+        let path = temp_path("split");
+        fs::write(&path, "").unwrap();
+        let mut follower = Follower::open(&path, None).unwrap();
+        // "中" 是 e4 b8 ad，第一次只写进前两个字节
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"\xe4\xb8").unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+        file.write_all(b"\xad\xe6\x96\x87\n").unwrap();
+        assert_eq!(vec!["中文"], follower.poll().unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn decodes_lines_with_configured_encoding() {
+        let path = temp_path("gbk");
+        fs::write(&path, "").unwrap();
+        let mut follower = Follower::open(&path, Some(Encoding::Gbk)).unwrap();
+        // GBK 的 "不是"
+        fs::write(&path, b"\xb2\xbb\xca\xc7\n").unwrap();
+        assert_eq!(vec!["不是"], follower.poll().unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn restarts_after_truncation() {
+        let path = temp_path("truncate");
+        fs::write(&path, "old line one\nold line two\n").unwrap();
+        let mut follower = Follower::open(&path, None).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+
+        fs::write(&path, "new\n").unwrap();
+        assert_eq!(vec!["new"], follower.poll().unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reopens_rotated_file() {
+        let path = temp_path("rotate");
+        let rotated = temp_path("rotate.1");
+        fs::write(&path, "before\n").unwrap();
+        let mut follower = Follower::open(&path, None).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+
+        append(&path, "tail of old\n");
+        fs::rename(&path, &rotated).unwrap();
+        fs::write(&path, "fresh\n").unwrap();
+        assert_eq!(vec!["tail of old", "fresh"], follower.poll().unwrap());
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&rotated).unwrap();
+    }
+
+    #[test]
+    fn reads_large_appends_in_bounded_chunks() {
+        let path = temp_path("large");
+        fs::write(&path, "").unwrap();
+        let mut follower = Follower::open(&path, None).unwrap();
+        // 每行 16 字节，合计 2.5 MiB
+        let line = "0123456789abcde\n";
+        let count = (5 << 20) / 2 / line.len();
+        append(&path, &line.repeat(count));
+
+        let mut polls = 0;
+        let mut lines = 0;
+        while lines < count {
+            let new = follower.poll().unwrap();
+            assert!(new.len() as u64 <= MAX_READ / line.len() as u64);
+            lines += new.len();
+            polls += 1;
+        }
+        assert_eq!(count, lines);
+        assert_eq!(3, polls);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+# 生成 normalize.bin：minigrep 做 Unicode 规范化（NFC/NFKC）用到的数据表
+# 用法：python3 gen_normalize_table.py（数据来自 Python 自带的 unicodedata）
+#
+# 每条记录以 1 字节类型开头，码点都用 3 字节小端整数表示：
+#   1 = 规范分解   码点, 长度(1 字节), 完全分解后的码点...
+#   2 = 兼容分解   格式同上，只收录和规范分解不同的字符
+#   3 = 组合类     码点, 组合类(1 字节)，只收录非 0 的
+#   4 = 组合       第一个码点, 第二个码点, 组合后的码点
+# 朝鲜文音节按算法分解和组合，不在表里
+import unicodedata
+
+HANGUL = range(0xAC00, 0xD7A4)
+
+
+def cp(c):
+    return ord(c).to_bytes(3, "little")
+
+
+out = bytearray()
+for code in range(0x110000):
+    if code in HANGUL or 0xD800 <= code < 0xE000:
+        continue
+    c = chr(code)
+    nfd = unicodedata.normalize("NFD", c)
+    nfkd = unicodedata.normalize("NFKD", c)
+    if nfd != c:
+        out += b"\x01" + cp(c) + bytes([len(nfd)]) + b"".join(cp(d) for d in nfd)
+    if nfkd != nfd:
+        out += b"\x02" + cp(c) + bytes([len(nfkd)]) + b"".join(cp(d) for d in nfkd)
+    ccc = unicodedata.combining(c)
+    if ccc:
+        out += b"\x03" + cp(c) + bytes([ccc])
+    # 只有单层规范分解恰好是两个字符、且 NFC 之后还是它自己的字符才参与组合（排除组合排除表里的）
+    decomposition = unicodedata.decomposition(c)
+    if decomposition and not decomposition.startswith("<"):
+        parts = [chr(int(p, 16)) for p in decomposition.split()]
+        if len(parts) == 2 and unicodedata.normalize("NFC", c) == c:
+            out += b"\x04" + cp(parts[0]) + cp(parts[1]) + cp(c)
+
+with open("normalize.bin", "wb") as f:
+    f.write(out)
+print(f"Unicode {unicodedata.unidata_version}: wrote {len(out)} bytes")
@@ -0,0 +1,321 @@
+// 三元组（trigram）索引：记录每个文件里出现过的所有连续 3 字节组合
+// query 的某个三元组不在文件里，文件就不可能包含 query，可以直接跳过
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::archive::ArchiveKind;
+use crate::collect_files;
+use crate::encoding;
+
+pub const DEFAULT_INDEX_FILE: &str = ".minigrep-index";
+
+const MAGIC: &[u8] = b"MGIDX1\n";
+
+pub type Trigram = u32;
+
+#[derive(Debug, PartialEq)]
+struct Entry {
+    // 修改时间（秒 + 纳秒）和大小都没变，就认为索引仍然有效
+    modified: (u64, u32),
+    size: u64,
+    // 排序后的三元组，查找时二分
+    trigrams: Vec<Trigram>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Index {
+    entries: HashMap<String, Entry>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct UpdateSummary {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    // 读不了的文件，"路径: 原因"
+    pub failures: Vec<String>,
+}
+
+pub fn trigrams(bytes: &[u8]) -> Vec<Trigram> {
+    let set: HashSet<Trigram> = bytes
+        .windows(3)
+        .map(|w| (w[0] as u32) << 16 | (w[1] as u32) << 8 | w[2] as u32)
+        .collect();
+    let mut trigrams: Vec<Trigram> = set.into_iter().collect();
+    trigrams.sort_unstable();
+    trigrams
+}
+
+// 同一个文件可能写成 ./src/a.rs、src//a.rs 或 src/a.rs，去掉 . 和多余的分隔符后再当键
+fn key(path: &str) -> String {
+    let key: PathBuf = Path::new(path).components().filter(|component| *component != Component::CurDir).collect();
+    key.to_string_lossy().into_owned()
+}
+
+// 旧条目仍然有效时原样返回，否则重新读文件；第二个值表示是否重新读过
+fn refresh(path: &str, old: Option<Entry>) -> io::Result<(Entry, bool)> {
+    let meta = fs::metadata(path)?;
+    let modified = stamp(&meta)?;
+    match old {
+        Some(entry) if entry.modified == modified && entry.size == meta.len() => Ok((entry, false)),
+        _ => {
+            let bytes = fs::read(path)?;
+            // 带 BOM 的 UTF-16 文件按解码后的 UTF-8 建索引，和搜索时看到的内容一致
+            let text = encoding::decode_all(&bytes, None)?;
+            Ok((Entry { modified, size: bytes.len() as u64, trigrams: trigrams(&text) }, true))
+        }
+    }
+}
+
+fn stamp(meta: &fs::Metadata) -> io::Result<(u64, u32)> {
+    let since_epoch = meta.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok((since_epoch.as_secs(), since_epoch.subsec_nanos()))
+}
+
+impl Index {
+    // 索引文件不存在时返回空索引
+    pub fn load(path: &str) -> io::Result<Index> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Index::default()),
+            Err(e) => return Err(e),
+        };
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a minigrep index file"));
+        }
+
+        let mut entries = HashMap::new();
+        let count = read_u64(&mut reader)?;
+        for _ in 0..count {
+            let mut path = vec![0; read_len(&mut reader, file_len, 1)?];
+            reader.read_exact(&mut path)?;
+            let path = String::from_utf8(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let modified = (read_u64(&mut reader)?, read_u32(&mut reader)?);
+            let size = read_u64(&mut reader)?;
+            let len = read_len(&mut reader, file_len, 4)?;
+            let trigrams = (0..len).map(|_| read_u32(&mut reader)).collect::<io::Result<_>>()?;
+            entries.insert(path, Entry { modified, size, trigrams });
+        }
+        Ok(Index { entries })
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        let mut paths: Vec<&String> = self.entries.keys().collect();
+        paths.sort();
+        for path in paths {
+            let entry = &self.entries[path];
+            writer.write_all(&(path.len() as u64).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&entry.modified.0.to_le_bytes())?;
+            writer.write_all(&entry.modified.1.to_le_bytes())?;
+            writer.write_all(&entry.size.to_le_bytes())?;
+            writer.write_all(&(entry.trigrams.len() as u64).to_le_bytes())?;
+            for trigram in &entry.trigrams {
+                writer.write_all(&trigram.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    // 增量更新：只重新读取修改时间或大小变了的文件，不在列表里的旧条目删除
+    // 读不了的文件记进 summary.failures，不留在索引里，其余的照常更新
+    pub fn update(&mut self, files: &[String]) -> UpdateSummary {
+        let mut summary = UpdateSummary::default();
+        let mut entries = HashMap::new();
+        for path in files {
+            // 压缩包不建索引，搜索时总是打开
+            if ArchiveKind::from_path(path).is_some() {
+                continue;
+            }
+            let key = key(path);
+            match refresh(path, self.entries.remove(&key)) {
+                Ok((entry, fresh)) => {
+                    if fresh {
+                        summary.indexed += 1;
+                    } else {
+                        summary.unchanged += 1;
+                    }
+                    entries.insert(key, entry);
+                }
+                Err(e) => summary.failures.push(format!("{path}: {e}")),
+            }
+        }
+        summary.removed = self.entries.len();
+        self.entries = entries;
+        summary
+    }
+
+    // 文件可能包含这些三元组时返回 true
+    // 没被索引过或索引已过期的文件无法判断，也返回 true，交给正常搜索
+    pub fn may_contain(&self, path: &str, query_trigrams: &[Trigram]) -> bool {
+        let Some(entry) = self.entries.get(&key(path)) else {
+            return true;
+        };
+        let fresh = fs::metadata(path)
+            .and_then(|meta| Ok(stamp(&meta)? == entry.modified && meta.len() == entry.size))
+            .unwrap_or(false);
+        !fresh || query_trigrams.iter().all(|t| entry.trigrams.binary_search(t).is_ok())
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+// 长度字段来自文件本身：len 个 item_size 字节的元素超出文件剩下的部分，说明文件坏了，不能照着它分配内存
+fn read_len(reader: &mut BufReader<File>, file_len: u64, item_size: u64) -> io::Result<usize> {
+    let len = read_u64(reader)?;
+    let remaining = file_len.saturating_sub(reader.stream_position()?);
+    if len.checked_mul(item_size).is_none_or(|bytes| bytes > remaining) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt minigrep index file"));
+    }
+    Ok(len as usize)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+// minigrep --build-index [--index FILE] [PATH...]
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut index_file = DEFAULT_INDEX_FILE.to_string();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--index" => index_file = args.next().ok_or("--index requires a file name")?.clone(),
+            flag if flag.starts_with('-') && flag.len() > 1 => return Err("unknown option".into()),
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(".".to_string());
+    }
+
+    let mut index = Index::load(&index_file)?;
+    let mut failures = Vec::new();
+    let files = collect_files(&paths, &mut failures);
+    let summary = index.update(&files);
+    failures.extend(summary.failures);
+    index.save(&index_file)?;
+    println!(
+        "Indexed {} files ({} unchanged, {} removed) into {index_file}",
+        summary.indexed, summary.unchanged, summary.removed
+    );
+    // 和搜索一样：其他文件照常建索引，但退出码要反映出有文件没读成
+    for failure in &failures {
+        eprintln!("minigrep: {failure}");
+    }
+    if !failures.is_empty() {
+        return Err(format!("{} file(s) could not be indexed", failures.len()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigrams_are_sorted_and_unique() {
+        assert_eq!(vec![0x616161], trigrams(b"aaaa"));
+        assert_eq!(2, trigrams(b"abcd").len());
+        assert!(trigrams(b"ab").is_empty());
+    }
+
+    #[test]
+    fn update_save_and_reload() {
+        let dir = std::env::temp_dir().join(format!("minigrep-index-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.txt").to_string_lossy().into_owned();
+        let b = dir.join("b.txt").to_string_lossy().into_owned();
+        let index_file = dir.join(DEFAULT_INDEX_FILE).to_string_lossy().into_owned();
+        fs::write(&a, "safe, fast, productive.").unwrap();
+        fs::write(&b, "Pick three.").unwrap();
+
+        let mut index = Index::default();
+        let missing = dir.join("missing.txt").to_string_lossy().into_owned();
+        let summary = index.update(&[a.clone(), missing.clone(), b.clone()]);
+        assert_eq!(2, summary.indexed);
+        assert_eq!(1, summary.failures.len());
+        assert!(summary.failures[0].starts_with(&format!("{missing}: ")), "{:?}", summary.failures);
+        index.save(&index_file).unwrap();
+
+        let mut index = Index::load(&index_file).unwrap();
+        assert!(index.may_contain(&a, &trigrams(b"duct")));
+        assert!(!index.may_contain(&b, &trigrams(b"duct")));
+        assert!(index.may_contain("not-indexed.txt", &trigrams(b"duct")));
+
+        // b 被改写后索引过期，不能再跳过
+        fs::write(&b, "Pick three products.").unwrap();
+        assert!(index.may_contain(&b, &trigrams(b"duct")));
+
+        let summary = index.update(std::slice::from_ref(&b));
+        assert_eq!(UpdateSummary { indexed: 1, unchanged: 0, removed: 1, failures: vec![] }, summary);
+        let summary = index.update(std::slice::from_ref(&b));
+        assert_eq!(UpdateSummary { indexed: 0, unchanged: 1, removed: 0, failures: vec![] }, summary);
+
+        // 换一种写法的同一个路径仍然对得上索引
+        let dotted = format!("{}/./b.txt", dir.display());
+        let summary = index.update(std::slice::from_ref(&dotted));
+        assert_eq!(UpdateSummary { indexed: 0, unchanged: 1, removed: 0, failures: vec![] }, summary);
+        assert!(!index.may_contain(&format!("{}//b.txt", dir.display()), &trigrams(b"safe")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keys_ignore_dot_components() {
+        assert_eq!(key("src/a.rs"), key("./src/a.rs"));
+        assert_eq!(key("src/a.rs"), key("src/./a.rs"));
+        assert_eq!(key("src/a.rs"), key("src//a.rs"));
+        assert_eq!("/tmp/a.rs", key("/tmp/./a.rs"));
+        // .. 可能穿过符号链接，不能直接和前一段抵消
+        assert_eq!("src/../a.rs", key("./src/../a.rs"));
+    }
+
+    #[test]
+    fn corrupt_lengths_are_rejected() {
+        let index_file = std::env::temp_dir().join(format!("minigrep-index-corrupt-{}", std::process::id()));
+        let index_file = index_file.to_string_lossy().into_owned();
+        // 一个条目，路径长度写成 2^62
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(1u64.to_le_bytes());
+        bytes.extend((1u64 << 62).to_le_bytes());
+        bytes.extend(b"a.txt");
+        fs::write(&index_file, &bytes).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, Index::load(&index_file).unwrap_err().kind());
+
+        // 路径正常，三元组个数比文件剩下的字节多
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(1u64.to_le_bytes());
+        bytes.extend(5u64.to_le_bytes());
+        bytes.extend(b"a.txt");
+        bytes.extend([0; 20]);
+        bytes.extend(u64::MAX.to_le_bytes());
+        fs::write(&index_file, &bytes).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, Index::load(&index_file).unwrap_err().kind());
+
+        fs::remove_file(&index_file).unwrap();
+    }
+
+    #[test]
+    fn missing_index_file_is_empty() {
+        assert_eq!(Index::default(), Index::load("/nonexistent/minigrep-index").unwrap());
+    }
+}
@@ -0,0 +1,358 @@
+// DEFLATE（RFC 1951）和 gzip（RFC 1952）解压，给 .tar.gz 和 .zip 用
+// 按 zlib 附带的 puff.c 的思路实现：逐位解码范式哈夫曼码，简单但足够快
+use std::io;
+
+const MAX_BITS: usize = 15;
+
+// 长度码 257..=285 和距离码 0..=29 的基础值与额外位数
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// 动态块里码长码的排列顺序
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// 解压结果超过调用方给的上限：几 KB 的压缩炸弹能展开成几十 GB，必须边解边查，不能解完再看
+fn too_large(limit: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("decompressed data is larger than {limit} bytes"))
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.bit_count < n {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid("unexpected end of deflate stream"))?;
+            self.pos += 1;
+            self.bit_buf |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buf & ((1u32 << n) - 1);
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        Ok(value)
+    }
+
+    // 存储块从字节边界开始，丢掉当前字节剩下的位
+    fn align(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+}
+
+// 范式哈夫曼表：每种码长有几个符号，以及按码值排好的符号
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        // 码长过多会超出码空间，属于损坏的数据；不完整的码（只有一个距离码时）是允许的
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(invalid("over-subscribed huffman code"));
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid huffman code"))
+    }
+}
+
+// 解压一段原始 DEFLATE 数据，返回解压结果和消耗的输入字节数；输出超过 limit 字节时报错
+pub fn inflate(data: &[u8], limit: usize) -> io::Result<(Vec<u8>, usize)> {
+    let mut out = Output { bytes: Vec::new(), limit };
+    let used = inflate_into(data, &mut out)?;
+    Ok((out.bytes, used))
+}
+
+// 解压结果追加到 out 后面，返回消耗的输入字节数
+fn inflate_into(data: &[u8], out: &mut Output) -> io::Result<usize> {
+    let mut reader = BitReader { data, pos: 0, bit_buf: 0, bit_count: 0 };
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => stored(&mut reader, out)?,
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                codes(&mut reader, out, &Huffman::new(&lengths)?, &Huffman::new(&[5; 30])?)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut reader)?;
+                codes(&mut reader, out, &lit, &dist)?;
+            }
+            _ => return Err(invalid("invalid deflate block type")),
+        }
+        if last {
+            return Ok(reader.pos);
+        }
+    }
+}
+
+// 带上限的输出缓冲区，每次写入前检查
+struct Output {
+    bytes: Vec<u8>,
+    limit: usize,
+}
+
+impl Output {
+    fn reserve(&self, len: usize) -> io::Result<()> {
+        if len > self.limit - self.bytes.len() {
+            return Err(too_large(self.limit));
+        }
+        Ok(())
+    }
+}
+
+fn stored(reader: &mut BitReader, out: &mut Output) -> io::Result<()> {
+    reader.align();
+    let header = reader.data.get(reader.pos..reader.pos + 4).ok_or_else(|| invalid("truncated stored block"))?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(invalid("stored block length check failed"));
+    }
+    let start = reader.pos + 4;
+    let block = reader.data.get(start..start + len as usize).ok_or_else(|| invalid("truncated stored block"))?;
+    out.reserve(block.len())?;
+    out.bytes.extend_from_slice(block);
+    reader.pos = start + len as usize;
+    Ok(())
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let nlen = reader.bits(5)? as usize + 257;
+    let ndist = reader.bits(5)? as usize + 1;
+    let ncode = reader.bits(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err(invalid("too many length or distance codes"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..ncode] {
+        code_lengths[i] = reader.bits(3)? as u8;
+    }
+    let code_huffman = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; nlen + ndist];
+    let mut i = 0;
+    while i < nlen + ndist {
+        let symbol = code_huffman.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..i].last().ok_or_else(|| invalid("repeat with no previous length"))?;
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(invalid("too many code lengths"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err(invalid("missing end-of-block code"));
+    }
+    Ok((Huffman::new(&lengths[..nlen])?, Huffman::new(&lengths[nlen..])?))
+}
+
+fn codes(reader: &mut BitReader, out: &mut Output, lit: &Huffman, dist: &Huffman) -> io::Result<()> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => {
+                out.reserve(1)?;
+                out.bytes.push(symbol as u8);
+            }
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err(invalid("invalid length code"));
+                }
+                let len = LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                let index = dist.decode(reader)? as usize;
+                if index >= DIST_BASE.len() {
+                    return Err(invalid("invalid distance code"));
+                }
+                let distance = DIST_BASE[index] as usize + reader.bits(DIST_EXTRA[index] as u32)? as usize;
+                if distance > out.bytes.len() {
+                    return Err(invalid("distance too far back"));
+                }
+                out.reserve(len)?;
+                // 复制区间可能和正在写入的部分重叠，只能逐字节复制
+                let start = out.bytes.len() - distance;
+                for i in 0..len {
+                    out.bytes.push(out.bytes[start + i]);
+                }
+            }
+        }
+    }
+}
+
+// gzip 文件可以由多个成员首尾相接组成，依次解压后拼在一起；合计超过 limit 字节时报错
+pub fn gunzip(mut data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let mut out = Output { bytes: Vec::new(), limit };
+    while !data.is_empty() {
+        if data.len() < 18 || data[0] != 0x1f || data[1] != 0x8b || data[2] != 8 {
+            return Err(invalid("not a gzip file"));
+        }
+        let flags = data[3];
+        let mut pos = 10;
+        if flags & 0x04 != 0 {
+            let extra = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+            pos += 2 + extra;
+        }
+        // FNAME、FCOMMENT 都是以 0 结尾的字符串
+        for flag in [0x08, 0x10] {
+            if flags & flag != 0 {
+                let end = data.get(pos..).and_then(|rest| rest.iter().position(|&b| b == 0));
+                pos += end.ok_or_else(|| invalid("truncated gzip header"))? + 1;
+            }
+        }
+        if flags & 0x02 != 0 {
+            pos += 2;
+        }
+        let start = out.bytes.len();
+        pos += inflate_into(data.get(pos..).ok_or_else(|| invalid("truncated gzip header"))?, &mut out)?;
+        let trailer = data.get(pos..pos + 8).ok_or_else(|| invalid("truncated gzip trailer"))?;
+        if u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) != crc32(&out.bytes[start..]) {
+            return Err(invalid("gzip checksum mismatch"));
+        }
+        data = &data[pos + 8..];
+    }
+    Ok(out.bytes)
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+    !data.iter().fold(!0u32, |crc, &b| TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_known_value() {
+        assert_eq!(0xCBF43926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn inflates_stored_block() {
+        let data = [0x01, 0x05, 0x00, 0xfa, 0xff, b'h', b'e', b'l', b'l', b'o'];
+        assert_eq!((b"hello".to_vec(), 10), inflate(&data, 5).unwrap());
+        assert_eq!("decompressed data is larger than 4 bytes", inflate(&data, 4).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn gunzips_fixed_huffman_block() {
+        // gzip.compress(b"safe, fast, productive.\nsafe, fast, productive.\n", mtime=0)
+        let data = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x2b, 0x4e, 0x4c, 0x4b, 0xd5, 0x51, 0x48, 0x4b,
+            0x2c, 0x2e, 0xd1, 0x51, 0x28, 0x28, 0xca, 0x4f, 0x29, 0x4d, 0x2e, 0xc9, 0x2c, 0x4b, 0xd5, 0xe3, 0x2a, 0xc6,
+            0x21, 0x0e, 0x00, 0x0a, 0xd7, 0x06, 0xcc, 0x30, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(b"safe, fast, productive.\nsafe, fast, productive.\n".to_vec(), gunzip(&data, 48).unwrap());
+        // 反向引用展开的部分同样受限
+        assert!(gunzip(&data, 47).is_err());
+    }
+
+    #[test]
+    fn gunzips_dynamic_huffman_block() {
+        // poem.txt 的前五行，长度足够让 gzip 选用动态哈夫曼块
+        let data = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x2d, 0x8e, 0x31, 0x0a, 0xc3, 0x30, 0x10, 0x04,
+            0x7b, 0xbd, 0x62, 0x5d, 0xa9, 0x71, 0xf2, 0x05, 0xe3, 0x2e, 0xe9, 0x03, 0xa9, 0x4f, 0xe8, 0x82, 0x8c, 0xed,
+            0xdb, 0x20, 0xc9, 0x18, 0xfd, 0x3e, 0x8e, 0x93, 0x6e, 0x61, 0x96, 0x61, 0xee, 0x7e, 0x85, 0x31, 0x30, 0xb6,
+            0x0e, 0xcf, 0x44, 0x48, 0x56, 0x34, 0x6e, 0x83, 0x1b, 0x7f, 0xe3, 0x0f, 0x7b, 0x54, 0x72, 0x70, 0x8f, 0xa4,
+            0x86, 0x9a, 0x34, 0xab, 0x2f, 0x10, 0xbc, 0x65, 0xca, 0xe0, 0x0b, 0x5b, 0xc1, 0x05, 0x91, 0xe6, 0x2b, 0xaa,
+            0x2e, 0x4b, 0xf7, 0xfd, 0x35, 0x1f, 0x11, 0xc4, 0xa6, 0x92, 0x0e, 0xdc, 0x9f, 0xaa, 0xd9, 0xb8, 0x5f, 0xdd,
+            0x8d, 0x3b, 0x62, 0x56, 0xc9, 0xed, 0x70, 0x22, 0x28, 0x0a, 0x57, 0x3d, 0x03, 0xdc, 0x07, 0xa8, 0x47, 0xdc,
+            0xe3, 0x8d, 0x00, 0x00, 0x00,
+        ];
+        let poem = include_str!("poem.txt");
+        let expected: String = poem.lines().step_by(2).take(5).map(|line| format!("{line}\n")).collect();
+        assert_eq!(expected.into_bytes(), gunzip(&data, 141).unwrap());
+
+        // 两个成员首尾相接
+        let mut twice = data.to_vec();
+        twice.extend_from_slice(&data);
+        assert_eq!(2 * 141, gunzip(&twice, 2 * 141).unwrap().len());
+        // 上限是所有成员合计
+        assert_eq!("decompressed data is larger than 200 bytes", gunzip(&twice, 200).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn rejects_corrupt_data() {
+        assert!(gunzip(b"plain text, not gzip", 1024).is_err());
+        assert!(inflate(&[0x07], 1024).is_err());
+    }
+}
@@ -1,31 +1,530 @@
 use std::error::Error;
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::time::Instant;
+
+pub mod archive;
+pub mod encoding;
+pub mod field;
+pub mod follow;
+pub mod index;
+pub mod inflate;
+pub mod normalize;
+pub mod pre;
+pub mod query;
+pub mod record;
+pub mod sort;
+pub mod stats;
+pub mod tui;
+
+use archive::{Archive, ArchiveKind};
+use encoding::Encoding;
+use field::Field;
+use index::{Index, Trigram};
+use normalize::Form;
+use pre::Preprocessor;
+use query::{ParseError, Query};
+use record::Terminator;
+use sort::{FileMatches, Sort, SortKey};
+use stats::{CountingReader, Stats};
+
+// -h / --help 打印的用法说明
+pub const USAGE: &str = r#"Usage: minigrep [OPTIONS] QUERY PATH...
+       minigrep --build-index [--index FILE] [PATH...]
+
+QUERY is a boolean expression:
+  error AND timeout         lines containing both terms
+  error OR warning          lines containing either term
+  NOT debug                 lines without the term
+  (a OR b) AND c            parentheses group; NOT binds tightest, then AND, then OR
+  "fn main()"               a quoted phrase is searched literally; inside quotes write \" for a quote and \\ for a backslash
+Words without operators form one phrase, so `safe, fast` matches that text as typed.
+Use -F to skip the expression syntax entirely: minigrep -F 'DO NOT' notes.txt
+
+Options:
+  -F, --fixed-strings       search for QUERY literally, no AND/OR/NOT, parentheses or quotes
+  -m, --max-count NUM       stop after NUM matches per file
+      --max-total NUM       stop after NUM matches in total
+      --field KEY[=QUERY]   match only the value of KEY in JSON lines
+      --column N            match only column N (from 1) of CSV lines
+      --crlf                records end with \r\n; a lone \n stays inside the record
+      --null-data           records end with NUL
+      --encoding NAME       read files without a BOM as utf-8, utf-16le, utf-16be, latin-1 or gbk
+      --normalize FORM      compare after Unicode normalization: nfc or nfkc
+      --pre COMMAND         search the output of COMMAND FILE instead of the file
+      --pre-glob GLOB       only run --pre on files matching GLOB (repeatable)
+      --sort KEY            sort files by path, modified, created or matches (--sortr reverses)
+      --heading             print each file name once above its matches
+      --index FILE          skip files that the trigram index rules out
+      --build-index         build or update the index (default .minigrep-index) instead of searching
+      --follow              keep printing matching lines appended to the files
+      --stats               print totals to stderr when done
+      --tui                 browse the results interactively
+  -h, --help                print this help
+Use -- before a QUERY or PATH that starts with -."#;
 
 pub struct Config {
     pub query: String,
-    pub file_path:String,
+    // query 解析出的布尔表达式，每一行都用它来判断是否匹配
+    pub expr: Query,
+    // -F / --fixed-strings：query 原样当作一个子串，不解析运算符、括号和引号
+    pub fixed_strings: bool,
+    pub file_paths: Vec<String>,
+    // --follow：像 tail -f 一样持续输出新追加的匹配行
+    pub follow: bool,
+    // -m NUM：每个文件最多输出 NUM 条匹配
+    pub max_count: Option<usize>,
+    // --max-total NUM：所有文件合计最多输出 NUM 条匹配
+    pub max_total: Option<usize>,
+    // --field KEY / --column N：只在 JSON 的某个 key 或 CSV 的某一列里匹配
+    pub field: Option<Field>,
+    // --stats：结束后把汇总信息打印到 stderr
+    pub stats: bool,
+    // --index FILE：用 `minigrep --build-index` 建好的三元组索引跳过不可能匹配的文件
+    pub index: Option<String>,
+    // --tui：在交互式界面里浏览结果
+    pub tui: bool,
+    // --encoding：没有 BOM 的文件按这个编码读取
+    pub encoding: Option<Encoding>,
+    // --normalize nfc|nfkc：query 和每一行都规范化之后再比较
+    pub normalize: Option<Form>,
+    // --sort KEY / --sortr KEY：按文件排序后再输出
+    pub sort: Option<Sort>,
+    // --heading：文件名单独占一行，下面列出它的匹配行
+    pub heading: bool,
+    // --pre COMMAND / --pre-glob GLOB：先把文件交给外部命令转换成文本
+    pub pre: Option<Preprocessor>,
+    // --crlf / --null-data：记录以什么结尾，输出时每条记录带回原来的行尾
+    pub terminator: Terminator,
 }
 
 impl Config {
-    pub fn build(args:&[String]) ->Result<Config,&'static str> {
-        if args.len()<3 {
-            return Err("not enough arguments");
+    pub fn build(args:&[String]) ->Result<Config,String> {
+        let mut positional = Vec::new();
+        let mut fixed_strings = false;
+        let mut follow = false;
+        let mut max_count = None;
+        let mut max_total = None;
+        let mut field = None;
+        let mut field_query = None;
+        let mut stats = false;
+        let mut index = None;
+        let mut tui = false;
+        let mut encoding = None;
+        let mut normalize = None;
+        let mut sort = None;
+        let mut heading = false;
+        let mut pre = None;
+        let mut pre_globs = Vec::new();
+        let mut terminator = Terminator::Line;
+
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-F" | "--fixed-strings" => fixed_strings = true,
+                "--follow" => follow = true,
+                "--stats" => stats = true,
+                "--tui" => tui = true,
+                "--heading" => heading = true,
+                "--crlf" | "--null-data" => {
+                    let chosen = if arg == "--crlf" { Terminator::Crlf } else { Terminator::Nul };
+                    if terminator != Terminator::Line && terminator != chosen {
+                        return Err("--crlf and --null-data cannot be used together".into());
+                    }
+                    terminator = chosen;
+                }
+                "--sort" | "--sortr" => {
+                    let label = args.next().ok_or_else(|| format!("{arg} requires path, modified, created or matches"))?;
+                    let key = SortKey::from_label(label)
+                        .ok_or_else(|| format!("unknown sort key {label} (expected path, modified, created or matches)"))?;
+                    sort = Some(Sort { key, reverse: arg == "--sortr" });
+                }
+                "-m" | "--max-count" => {
+                    max_count = Some(parse_count(args.next()).ok_or("-m requires a non-negative number")?);
+                }
+                "--max-total" => {
+                    max_total = Some(parse_count(args.next()).ok_or("--max-total requires a non-negative number")?);
+                }
+                "--field" => {
+                    let spec = args.next().ok_or("--field requires a key")?;
+                    // --field level=error 同时给出 key 和 query
+                    let key = match spec.split_once('=') {
+                        Some((key, query)) => {
+                            field_query = Some(query.to_string());
+                            key
+                        }
+                        None => spec,
+                    };
+                    if key.is_empty() {
+                        return Err("--field requires a key".into());
+                    }
+                    field = Some(Field::json(key));
+                }
+                "--index" => index = Some(args.next().ok_or("--index requires a file name")?.clone()),
+                "--encoding" => {
+                    let label = args.next().ok_or("--encoding requires a name")?;
+                    let parsed = Encoding::from_label(label)
+                        .ok_or_else(|| format!("unknown encoding {label} (expected utf-8, utf-16le, utf-16be, latin-1 or gbk)"))?;
+                    encoding = Some(parsed);
+                }
+                "--normalize" => {
+                    let label = args.next().ok_or("--normalize requires nfc or nfkc")?;
+                    normalize = Some(Form::from_label(label).ok_or_else(|| format!("unknown normalization form {label} (expected nfc or nfkc)"))?);
+                }
+                "--pre" => {
+                    let command = args.next().ok_or("--pre requires a command")?;
+                    pre = Some(Preprocessor::new(command).ok_or("--pre requires a command")?);
+                }
+                "--pre-glob" => pre_globs.push(args.next().ok_or("--pre-glob requires a pattern")?.clone()),
+                "--column" => {
+                    let column = parse_count(args.next()).filter(|&n| n > 0).ok_or("--column requires a column number starting at 1")?;
+                    field = Some(Field::Csv(column));
+                }
+                // "--" 之后的参数都当作普通参数，方便搜索以 - 开头的字符串
+                "--" => positional.extend(args.by_ref().cloned()),
+                flag if flag.starts_with('-') && flag.len() > 1 => return Err(format!("unknown option {flag}")),
+                _ => positional.push(arg.clone()),
+            }
+        }
+
+        let query = match field_query {
+            Some(query) => query,
+            None if !positional.is_empty() => positional.remove(0),
+            None => return Err("not enough arguments".into()),
+        };
+        if positional.is_empty() {
+            return Err("not enough arguments".into());
+        }
+        let pre = match pre {
+            Some(pre) => Some(Preprocessor { globs: pre_globs, ..pre }),
+            None if !pre_globs.is_empty() => return Err("--pre-glob requires --pre".into()),
+            None => None,
+        };
+        if follow && terminator != Terminator::Line {
+            return Err("--follow does not support --crlf or --null-data".into());
+        }
+        // --follow 按 \n 字节切行，UTF-16 的换行是两个字节，切不准
+        if follow && matches!(encoding, Some(Encoding::Utf16Le | Encoding::Utf16Be)) {
+            return Err("--follow does not support UTF-16 input".into());
+        }
+        let expr = parse_expr(&query, fixed_strings, normalize).map_err(|e| format!("invalid query: {e}"))?;
+        let file_paths = positional;
+        Ok(Config { query, expr, fixed_strings, file_paths, follow, max_count, max_total, field, stats, index, tui, encoding, normalize, sort, heading, pre, terminator })
+    }
+
+    // 换一个 query，同时重新解析表达式；解析失败时原来的 query 保持不变
+    pub fn set_query(&mut self, query: String) -> Result<(), ParseError> {
+        self.expr = parse_expr(&query, self.fixed_strings, self.normalize)?;
+        self.query = query;
+        Ok(())
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        match &self.field {
+            Some(field) => field.extract(line).is_some_and(|value| self.matches_text(&value)),
+            None => self.matches_text(line),
+        }
+    }
+
+    fn matches_text(&self, text: &str) -> bool {
+        match self.normalize {
+            Some(form) => self.expr.matches(&normalize::normalize(text, form)),
+            None => self.expr.matches(text),
+        }
+    }
+
+    // 当前文件还能输出多少条：取 -m 和 --max-total 剩余额度中较小的那个
+    pub fn remaining(&self, file_count: usize, total: usize) -> Option<usize> {
+        let per_file = self.max_count.map(|max| max.saturating_sub(file_count));
+        let overall = self.max_total.map(|max| max.saturating_sub(total));
+        match (per_file, overall) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    // 多个文件、搜索目录或压缩包时像 grep 一样在每行前加上文件名
+    // 每次调用都会 stat 所有路径，调用方应只算一次
+    pub fn show_file_names(&self) -> bool {
+        self.file_paths.len() > 1
+            || self.file_paths.iter().any(|path| Path::new(path).is_dir() || ArchiveKind::from_path(path).is_some())
+    }
+
+    // 匹配行一定包含的三元组；按字段匹配时行内文本可能带转义，不能用索引过滤
+    // 索引只按 BOM 解码，指定了其他编码时文件内容和索引对不上，也不能用
+    // 规范化后的 query 可能和文件里的原始写法字节不同，同样不能用；--pre 搜的是命令输出，也不能用
+    pub fn query_trigrams(&self) -> Option<Vec<Trigram>> {
+        if self.field.is_some()
+            || self.normalize.is_some()
+            || self.pre.is_some()
+            || self.encoding.is_some_and(|encoding| encoding != Encoding::Utf8)
+        {
+            return None;
         }
-        let query =args[1].clone();
-        let file_path = args[2].clone();
-        Ok(Config { query, file_path})
+        let mut trigrams: Vec<Trigram> = self
+            .expr
+            .required_terms()
+            .iter()
+            .flat_map(|term| index::trigrams(term.as_bytes()))
+            .collect();
+        trigrams.sort_unstable();
+        trigrams.dedup();
+        Some(trigrams)
     }
 }
+
+fn parse_expr(query: &str, fixed_strings: bool, normalize: Option<Form>) -> Result<Query, ParseError> {
+    let expr = if fixed_strings { Query::Term(query.to_string()) } else { Query::parse(query)? };
+    Ok(match normalize {
+        Some(form) => expr.map_terms(&|term| normalize::normalize(term, form).into_owned()),
+        None => expr,
+    })
+}
+
+fn parse_count(value: Option<&String>) -> Option<usize> {
+    value?.parse().ok()
+}
+
 pub fn run(config:Config) ->Result<(),Box<dyn Error>> {
+    if config.follow {
+        return follow::follow(&config);
+    }
+    if config.tui {
+        return tui::run(config);
+    }
+
+    let mut printer = Printer { config: &config, show_file_names: config.show_file_names(), last_path: None };
+    let stats = match config.sort {
+        None => search_files(&config, |path, _, line, ending| printer.print(path, line, ending))?,
+        // 排序需要先拿到全部结果
+        Some(sort) => {
+            let mut groups: Vec<FileMatches> = Vec::new();
+            let stats = search_files(&config, |path, line_number, line, ending| {
+                let record = (line_number, line.to_string(), ending);
+                match groups.last_mut() {
+                    Some(group) if group.path == path => group.lines.push(record),
+                    _ => groups.push(FileMatches { path: path.to_string(), lines: vec![record] }),
+                }
+            })?;
+            sort::sort_groups(&mut groups, sort);
+            for group in &groups {
+                for (_, line, ending) in &group.lines {
+                    printer.print(&group.path, line, ending);
+                }
+            }
+            stats
+        }
+    };
+    for failure in &stats.failures {
+        eprintln!("minigrep: {failure}");
+    }
+    if config.stats {
+        eprintln!("{stats}");
+    }
+    // 其他文件的结果已经输出了，但有文件没搜成时退出码要反映出来
+    if !stats.failures.is_empty() {
+        return Err(format!("{} file(s) could not be searched", stats.failures.len()).into());
+    }
+    Ok(())
+}
 
-    let contents = fs::read_to_string(config.file_path)?;
-    // println!("With text:\n {contents}");
-    for line in search(&config.query, &contents){
-        println!("{line}")
+struct Printer<'a> {
+    config: &'a Config,
+    // 构造时算好，不用每打印一行都 stat 一遍所有路径
+    show_file_names: bool,
+    last_path: Option<String>,
+}
+
+impl Printer<'_> {
+    // 每条记录后面写回它原来的行尾（\n、\r\n 或 NUL）
+    fn print(&mut self, path: &str, line: &str, ending: &str) {
+        if self.config.heading {
+            // 换文件时先空一行，再打印文件名
+            if self.last_path.as_deref() != Some(path) {
+                if self.last_path.is_some() {
+                    println!();
+                }
+                println!("{path}");
+                self.last_path = Some(path.to_string());
+            }
+            print!("{line}{ending}");
+        } else if self.show_file_names {
+            print!("{path}:{line}{ending}");
+        } else {
+            print!("{line}{ending}");
+        }
     }
+}
+
+// 依次搜索 config 里的所有文件，每个匹配行回调一次 on_match(文件名, 行号, 行, 行尾)
+pub fn search_files(config: &Config, mut on_match: impl FnMut(&str, usize, &str, &'static str)) -> Result<Stats, Box<dyn Error>> {
+    let start = Instant::now();
+    let mut stats = Stats::default();
+    let index = match &config.index {
+        Some(index_file) => Some(Index::load(index_file)?),
+        None => None,
+    };
+    let query_trigrams = config.query_trigrams();
+    for path in &collect_files(&config.file_paths, &mut stats.failures) {
+        let limit = config.remaining(0, stats.lines_matched);
+        if limit == Some(0) {
+            break;
+        }
+        if let (Some(index), Some(query_trigrams)) = (&index, &query_trigrams) {
+            if !index.may_contain(path, query_trigrams) {
+                stats.files_skipped += 1;
+                continue;
+            }
+        }
+        if let Some(pre) = config.pre.as_ref().filter(|pre| pre.applies_to(path)) {
+            // 命令失败只跳过这个文件，记下原因，不中断整个搜索
+            let output = match pre.run(path) {
+                Ok(output) => output,
+                Err(e) => {
+                    stats.failures.push(format!("{path}: {e}"));
+                    continue;
+                }
+            };
+            stats.bytes_read += output.len() as u64;
+            let mut matched = 0;
+            let searched = search_text(&output[..], config, limit, &mut matched, |line_number, line, ending| on_match(path, line_number, line, ending));
+            stats.record(path, matched, searched);
+            continue;
+        }
+        // 压缩包里的每个文件单独搜索，显示成 archive.zip!path/inside.txt
+        if let Some(kind) = ArchiveKind::from_path(path) {
+            // 压缩包打不开或损坏时记下原因，跳过整个压缩包；archive 的错误信息不带路径，统一在这里加
+            let mut members = match Archive::open(path, kind, &mut stats.bytes_read) {
+                Ok(members) => members,
+                Err(e) => {
+                    stats.failures.push(format!("{path}: {e}"));
+                    continue;
+                }
+            };
+            // 一次只解压一个文件，达到 -m 上限后剩下的不再解压
+            while config.remaining(0, stats.lines_matched) != Some(0) {
+                let limit = config.remaining(0, stats.lines_matched);
+                let member = match members.next() {
+                    Some(Ok(member)) => member,
+                    Some(Err(e)) => {
+                        stats.failures.push(format!("{path}: {e}"));
+                        continue;
+                    }
+                    None => break,
+                };
+                // 里面的二进制文件（图片、可执行文件等）由 search_text 整个跳过
+                let display = format!("{path}!{}", member.name);
+                let mut matched = 0;
+                let searched = search_text(&member.data[..], config, limit, &mut matched, |line_number, line, ending| {
+                    on_match(&display, line_number, line, ending)
+                });
+                stats.record(&display, matched, searched);
+            }
+            continue;
+        }
+        // 打不开、读不了的文件记下原因接着搜下一个，和 --pre 失败一样
+        let mut matched = 0;
+        let searched = File::open(path).and_then(|file| {
+            let reader = BufReader::new(CountingReader::new(file, &mut stats.bytes_read));
+            search_text(reader, config, limit, &mut matched, |line_number, line, ending| on_match(path, line_number, line, ending))
+        });
+        stats.record(path, matched, searched);
+    }
+    stats.elapsed = start.elapsed();
+    Ok(stats)
+}
+
+// 解码并搜索一个文件的内容，开头看起来是二进制的文件整个跳过，一行都不输出
+// 读到一半出错时，出错之前已经输出的匹配行照样计入 matched，统计和输出对得上
+fn search_text<R: BufRead>(
+    reader: R,
+    config: &Config,
+    limit: Option<usize>,
+    matched: &mut usize,
+    mut on_match: impl FnMut(usize, &str, &'static str),
+) -> io::Result<()> {
+    let mut reader = encoding::decode(reader, config.encoding)?;
+    if looks_binary(reader.fill_buf()?, config.terminator) {
+        return Ok(());
+    }
+    search_reader(reader, config.terminator, limit, |line| config.is_match(line), |line_number, line, ending| {
+        *matched += 1;
+        on_match(line_number, line, ending);
+    })?;
     Ok(())
 }
 
+// 和 grep 一样只看开头的一块：有 NUL（--null-data 时 NUL 是分隔符，不算）或者不是合法的 UTF-8 就当作二进制
+// 末尾被截断的多字节字符不算非法
+fn looks_binary(prefix: &[u8], terminator: Terminator) -> bool {
+    (terminator != Terminator::Nul && prefix.contains(&0)) || std::str::from_utf8(prefix).is_err_and(|e| e.error_len().is_some())
+}
+
+// 把参数里的目录递归展开成文件列表，按路径排序保证输出顺序稳定
+// 目录下以 . 开头的隐藏文件和目录（.git、索引文件等）会被跳过
+// 读不了的目录记进 failures（"路径: 原因"），其余的照常展开
+pub fn collect_files(paths: &[String], failures: &mut Vec<String>) -> Vec<String> {
+    let mut files = Vec::new();
+    for path in paths {
+        walk(Path::new(path), &mut files, failures);
+    }
+    files
+}
+
+fn walk(path: &Path, files: &mut Vec<String>, failures: &mut Vec<String>) {
+    if !path.is_dir() {
+        files.push(path.to_string_lossy().into_owned());
+        return;
+    }
+    let entries = fs::read_dir(path).and_then(|entries| entries.map(|entry| entry.map(|entry| entry.path())).collect());
+    let mut entries: Vec<_> = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            failures.push(format!("{}: {e}", path.display()));
+            return;
+        }
+    };
+    entries.sort();
+    for entry in entries {
+        if entry.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+            continue;
+        }
+        // 和 grep -r 一样，目录里指向目录的符号链接不跟进去，a/loop -> a 这种环才不会无限递归
+        // 参数里直接给的符号链接照常展开
+        if entry.is_symlink() && entry.is_dir() {
+            continue;
+        }
+        walk(&entry, files, failures);
+    }
+}
+
+// 逐条记录读取并匹配，达到 limit 后立即停止读取，大文件不必读完
+// on_match(行号, 不含行尾的记录, 原来的行尾)
+pub fn search_reader<R: BufRead>(
+    reader: R,
+    terminator: Terminator,
+    limit: Option<usize>,
+    is_match: impl Fn(&str) -> bool,
+    mut on_match: impl FnMut(usize, &str, &'static str),
+) -> io::Result<usize> {
+    let mut count = 0;
+    if limit == Some(0) {
+        return Ok(count);
+    }
+    for (i, record) in record::records(reader, terminator).enumerate() {
+        let (line, ending) = record?;
+        if is_match(&line) {
+            on_match(i + 1, &line, ending);
+            count += 1;
+            if Some(count) == limit {
+                break;
+            }
+        }
+    }
+    Ok(count)
+}
+
 pub fn search<'a>(query:&str,contents:&'a str) ->Vec<&'a str>{
     let mut results =Vec::new();
     for line in contents.lines() {  
@@ -48,4 +547,321 @@ safe, fast, productive.
 Pick three.";
     assert_eq!(vec!["safe, fast, productive."], search(query, contents));
     }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn build_reads_follow_flag(){
+        let config = Config::build(&args(&["minigrep", "--follow", "error", "app.log"])).unwrap();
+        assert!(config.follow);
+        assert_eq!("error", config.query);
+        assert_eq!(vec!["app.log"], config.file_paths);
+        assert!(Config::build(&args(&["minigrep", "--follow", "--encoding", "gbk", "error", "app.log"])).is_ok());
+        assert!(Config::build(&args(&["minigrep", "--follow", "--encoding", "utf-16be", "error", "app.log"])).is_err());
+    }
+
+    #[test]
+    fn build_reads_max_counts(){
+        let config = Config::build(&args(&["minigrep", "-m", "2", "--max-total", "3", "error", "a.log", "b.log"])).unwrap();
+        assert_eq!(Some(2), config.max_count);
+        assert_eq!(Some(3), config.max_total);
+        assert_eq!(vec!["a.log", "b.log"], config.file_paths);
+        assert_eq!(Some(2), config.remaining(0, 0));
+        assert_eq!(Some(1), config.remaining(0, 2));
+        assert_eq!(Some(0), config.remaining(2, 2));
+        assert_eq!(Some(0), config.remaining(0, 5));
+    }
+
+    #[test]
+    fn build_reads_field_options(){
+        let config = Config::build(&args(&["minigrep", "--field", "level=error", "a.jsonl"])).unwrap();
+        assert_eq!("error", config.query);
+        assert_eq!(vec!["a.jsonl"], config.file_paths);
+        assert!(config.is_match(r#"{"level":"error","msg":"ok"}"#));
+        assert!(!config.is_match(r#"{"level":"info","msg":"error"}"#));
+
+        let config = Config::build(&args(&["minigrep", "--column", "2", "Smith", "people.csv"])).unwrap();
+        assert_eq!(Some(Field::Csv(2)), config.field);
+        assert!(config.is_match(r#"1,"Smith, John",Smithfield"#));
+        assert!(!config.is_match("Smith,John"));
+    }
+
+    #[test]
+    fn build_rejects_bad_field_options(){
+        assert!(Config::build(&args(&["minigrep", "--column", "0", "x", "a.csv"])).is_err());
+        assert!(Config::build(&args(&["minigrep", "--field", "=x", "a.jsonl"])).is_err());
+        assert!(Config::build(&args(&["minigrep", "--field", "level=error"])).is_err());
+    }
+
+    #[test]
+    fn search_files_collects_stats(){
+        let dir = std::env::temp_dir();
+        let a = dir.join(format!("minigrep-stats-{}-a.txt", std::process::id()));
+        let b = dir.join(format!("minigrep-stats-{}-b.txt", std::process::id()));
+        std::fs::write(&a, "duct\nnone\nduct\n").unwrap();
+        std::fs::write(&b, "duct\n").unwrap();
+
+        let mut config = Config::build(&args(&["minigrep", "--stats", "duct", a.to_str().unwrap(), b.to_str().unwrap()])).unwrap();
+        assert!(config.stats);
+        let mut found = Vec::new();
+        let stats = search_files(&config, |_, _, line, _| found.push(line.to_string())).unwrap();
+        assert_eq!(3, found.len());
+        assert_eq!(2, stats.files_scanned);
+        assert_eq!(20, stats.bytes_read);
+        assert_eq!(3, stats.lines_matched);
+
+        // 额度在第一个文件里就用完了，第二个文件不会被扫描
+        config.max_total = Some(1);
+        let stats = search_files(&config, |_, _, _, _| {}).unwrap();
+        assert_eq!(1, stats.files_scanned);
+        assert_eq!(1, stats.lines_matched);
+
+        std::fs::remove_file(&a).unwrap();
+        std::fs::remove_file(&b).unwrap();
+    }
+
+    #[test]
+    fn search_files_keeps_going_past_unreadable_files(){
+        let dir = std::env::temp_dir().join(format!("minigrep-unreadable-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // 开头是正常文本，第一块之后才出现非法 UTF-8：前面输出过的匹配要算进统计
+        let mut late = "duct\n".repeat(3000).into_bytes();
+        late.extend(b"\xff\xfe\n");
+        fs::write(dir.join("a-late.txt"), late).unwrap();
+        fs::write(dir.join("b.txt"), "duct\n").unwrap();
+        let missing = dir.join("missing.txt").to_string_lossy().into_owned();
+        let root = dir.to_string_lossy().into_owned();
+
+        let config = Config::build(&args(&["minigrep", "duct", &missing, &root])).unwrap();
+        let mut found = 0;
+        let stats = search_files(&config, |_, _, _, _| found += 1).unwrap();
+        assert_eq!(3001, found);
+        assert_eq!(found, stats.lines_matched);
+        assert_eq!(1, stats.files_scanned);
+        assert_eq!(2, stats.failures.len());
+        assert!(stats.failures[0].starts_with(&format!("{missing}: ")));
+        assert!(stats.failures[1].starts_with(&format!("{}: ", dir.join("a-late.txt").display())));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn search_files_skips_directory_symlinks_and_unreadable_directories(){
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = std::env::temp_dir().join(format!("minigrep-walk-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::create_dir_all(dir.join("locked")).unwrap();
+        fs::write(dir.join("sub/a.txt"), "duct\n").unwrap();
+        fs::write(dir.join("locked/b.txt"), "duct\n").unwrap();
+        // 指回上层的环，跟进去会一直递归到栈溢出
+        symlink(&dir, dir.join("sub/loop")).unwrap();
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+        // root 不受权限位限制，这时 locked 照样能读
+        let locked = fs::read_dir(dir.join("locked")).is_err();
+
+        let root = dir.to_string_lossy().into_owned();
+        let config = Config::build(&args(&["minigrep", "duct", &root])).unwrap();
+        let mut found = Vec::new();
+        let stats = search_files(&config, |path, _, _, _| found.push(path.to_string())).unwrap();
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let a = dir.join("sub/a.txt").to_string_lossy().into_owned();
+        if locked {
+            assert_eq!(vec![a], found);
+            assert_eq!(1, stats.failures.len(), "{:?}", stats.failures);
+            assert!(stats.failures[0].starts_with(&format!("{}: ", dir.join("locked").display())));
+        } else {
+            assert_eq!(vec![dir.join("locked/b.txt").to_string_lossy().into_owned(), a], found);
+            assert!(stats.failures.is_empty(), "{:?}", stats.failures);
+        }
+    }
+
+    #[test]
+    fn search_files_skips_files_ruled_out_by_index(){
+        let dir = std::env::temp_dir().join(format!("minigrep-search-index-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("poem.txt"), "safe, fast, productive.\n").unwrap();
+        fs::write(dir.join("sub/other.txt"), "Pick three.\n").unwrap();
+        let root = dir.to_string_lossy().into_owned();
+        let index_file = dir.join(".minigrep-index").to_string_lossy().into_owned();
+        index::run(&args(&["--index", &index_file, &root])).unwrap();
+
+        let config = Config::build(&args(&["minigrep", "--index", &index_file, "duct", &root])).unwrap();
+        assert!(config.show_file_names());
+        let mut found = Vec::new();
+        let stats = search_files(&config, |path, line_number, line, _| found.push(format!("{path}:{line_number}:{line}"))).unwrap();
+        assert_eq!(vec![format!("{}:1:safe, fast, productive.", dir.join("poem.txt").display())], found);
+        assert_eq!(1, stats.files_scanned);
+        assert_eq!(1, stats.files_skipped);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn search_files_looks_inside_archives(){
+        let path = std::env::temp_dir().join(format!("minigrep-archive-{}.tar.gz", std::process::id()));
+        // tarfile + gzip 生成：docs/a.txt、二进制的 logo.bin 和 b.txt
+        fs::write(&path, b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xed\xd5\xbf\x0a\x82\x40\x00\xc7\xf1\x9b\x7d\x8a\x1b\x1b\xc4\x34\xed\x82\
+\xe6\xa0\x2d\xa2\x37\x38\xff\x85\x10\x29\x7a\x46\xaf\xd0\x73\xf5\x62\x5d\x2e\x82\x4b\x2d\x0a\xe5\xf7\xb3\xdc\xe1\
+\x22\x37\x7c\xf9\xa5\x65\xd2\x2c\xb5\x67\xee\x46\x8c\xc6\xb7\x54\x14\x75\xa7\x35\x3c\x7d\x3f\x54\xfd\xbd\xfb\xbe\
+\x51\xc1\x5a\x48\x5f\x4c\xa0\x6d\x8c\xae\xa5\x14\x33\x75\xb2\xef\xdf\x3a\x8d\xce\x33\x57\xe6\xba\x31\xae\xac\xea\
+\x32\x6d\x13\x53\xdc\x32\xcf\x11\xf8\x73\x97\xf2\x5c\x7a\x71\x71\x1d\xf3\x1f\x1f\xfb\x0f\xc2\x61\xff\x61\x18\xd0\
+\xff\x14\x1e\xc7\xc3\x5e\xbc\x7b\x7f\x2e\x88\x61\x86\xe2\x71\xa7\xff\xbb\xfe\x57\xc3\xfd\x57\x4a\xb1\xff\x93\xd8\
+\xd9\xf4\xa5\xd1\x95\x1d\xfb\x7e\xf8\xd9\x7d\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\xf8\x39\x2f\xb7\xb0\x1e\x4e\
+\x00\x28\x00\x00").unwrap();
+        let archive = path.to_string_lossy().into_owned();
+        let config = Config::build(&args(&["minigrep", "duct", &archive])).unwrap();
+        assert!(config.show_file_names());
+        let mut found = Vec::new();
+        let stats = search_files(&config, |path, line_number, line, _| found.push(format!("{path}:{line_number}:{line}"))).unwrap();
+        assert_eq!(vec![format!("{archive}!docs/a.txt:2:safe, fast, productive."), format!("{archive}!b.txt:2:productive")], found);
+        assert_eq!(3, stats.files_scanned);
+        assert_eq!(200, stats.bytes_read);
+
+        // 截断的压缩包记进 failures，不中断后面的文件
+        let other = std::env::temp_dir().join(format!("minigrep-archive-{}.txt", std::process::id()));
+        fs::write(&other, "duct\n").unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        let config = Config::build(&args(&["minigrep", "duct", &archive, other.to_str().unwrap()])).unwrap();
+        let stats = search_files(&config, |_, _, _, _| {}).unwrap();
+        assert_eq!(1, stats.lines_matched);
+        assert_eq!(1, stats.failures.len());
+        assert!(stats.failures[0].starts_with(&format!("{archive}: ")), "{:?}", stats.failures);
+        // 路径只出现一次
+        assert_eq!(1, stats.failures[0].matches(&archive).count(), "{:?}", stats.failures);
+        fs::remove_file(&other).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn search_files_runs_pre_command(){
+        let dir = std::env::temp_dir().join(format!("minigrep-pre-search-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.doc"), "tape\nother\n").unwrap();
+        fs::write(dir.join("b.txt"), "tape\n").unwrap();
+        let root = dir.to_string_lossy().into_owned();
+
+        // 只有 .doc 经过命令，tape 被替换成 duct 之后才能匹配
+        let config = Config::build(&args(&["minigrep", "--pre", "sed s/tape/duct/", "--pre-glob", "*.doc", "duct", &root])).unwrap();
+        assert!(config.query_trigrams().is_none());
+        let mut found = Vec::new();
+        let stats = search_files(&config, |path, line_number, line, _| found.push(format!("{path}:{line_number}:{line}"))).unwrap();
+        assert_eq!(vec![format!("{}:1:duct", dir.join("a.doc").display())], found);
+        assert_eq!(2, stats.files_scanned);
+
+        // 命令失败的文件被跳过并记下原因，其他文件照常搜索
+        let config = Config::build(&args(&["minigrep", "--pre", "false", "--pre-glob", "*.doc", "tape", &root])).unwrap();
+        let stats = search_files(&config, |_, _, _, _| {}).unwrap();
+        assert_eq!(1, stats.lines_matched);
+        assert_eq!(vec![format!("{}: false exited with exit status: 1", dir.join("a.doc").display())], stats.failures);
+
+        assert!(Config::build(&args(&["minigrep", "--pre-glob", "*.pdf", "x", "a.txt"])).is_err());
+        assert!(Config::build(&args(&["minigrep", "--pre", " ", "x", "a.txt"])).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_parses_query_expression(){
+        let config = Config::build(&args(&["minigrep", "error AND timeout AND NOT retry", "app.log"])).unwrap();
+        assert!(config.is_match("error: timeout after 30s"));
+        assert!(!config.is_match("error: timeout, retry 1/3"));
+
+        let err = Config::build(&args(&["minigrep", "error AND", "app.log"])).err().unwrap();
+        assert_eq!("invalid query: expected a search term after 'AND' at column 10", err);
+    }
+
+    #[test]
+    fn fixed_strings_skip_query_parsing(){
+        assert!(Config::build(&args(&["minigrep", "DO NOT", "notes.txt"])).is_err());
+        let config = Config::build(&args(&["minigrep", "-F", "DO NOT", "notes.txt"])).unwrap();
+        assert_eq!(Query::Term("DO NOT".to_string()), config.expr);
+        assert!(config.is_match("DO NOT EDIT"));
+
+        let mut config = Config::build(&args(&["minigrep", "--fixed-strings", "--normalize", "nfc", "fn main()", "a.rs"])).unwrap();
+        assert!(config.is_match("pub fn main() {"));
+        config.set_query("a AND".to_string()).unwrap();
+        assert!(config.is_match("a AND b"));
+        assert!(!config.is_match("a b"));
+    }
+
+    #[test]
+    fn field_search_uses_query_expression(){
+        let config = Config::build(&args(&["minigrep", "--field", "level", "error OR warn", "app.jsonl"])).unwrap();
+        assert!(config.is_match(r#"{"level":"warn"}"#));
+        assert!(!config.is_match(r#"{"level":"info","msg":"error"}"#));
+    }
+
+    #[test]
+    fn search_files_transcodes_input(){
+        let path = std::env::temp_dir().join(format!("minigrep-encoding-{}.txt", std::process::id()));
+        // GBK 编码的 "我啥也不是\n你呢\n"
+        fs::write(&path, b"\xce\xd2\xc9\xb6\xd2\xb2\xb2\xbb\xca\xc7\n\xc4\xe3\xc4\xd8\n").unwrap();
+        let config = Config::build(&args(&["minigrep", "--encoding", "gbk", "不是", path.to_str().unwrap()])).unwrap();
+        let mut found = Vec::new();
+        let stats = search_files(&config, |_, _, line, _| found.push(line.to_string())).unwrap();
+        assert_eq!(vec!["我啥也不是"], found);
+        assert_eq!(16, stats.bytes_read);
+        fs::remove_file(&path).unwrap();
+
+        assert!(Config::build(&args(&["minigrep", "--encoding", "ebcdic", "x", "a.txt"])).is_err());
+    }
+
+    #[test]
+    fn normalize_matches_equivalent_spellings(){
+        let config = Config::build(&args(&["minigrep", "--normalize", "nfc", "café", "menu.txt"])).unwrap();
+        assert!(config.is_match("un cafe\u{301} noir"));
+        assert!(config.query_trigrams().is_none());
+        assert!(!Config::build(&args(&["minigrep", "café", "menu.txt"])).unwrap().is_match("un cafe\u{301} noir"));
+
+        let config = Config::build(&args(&["minigrep", "--normalize", "NFKC", "file", "a.txt"])).unwrap();
+        assert!(config.is_match("ｆｉｌｅ"));
+        assert!(config.is_match("\u{FB01}le"));
+        assert!(Config::build(&args(&["minigrep", "--normalize", "nfd", "x", "a.txt"])).is_err());
+    }
+
+    #[test]
+    fn build_reads_sort_options(){
+        let config = Config::build(&args(&["minigrep", "--sortr", "matches", "--heading", "x", "src"])).unwrap();
+        assert_eq!(Some(Sort { key: SortKey::Matches, reverse: true }), config.sort);
+        assert!(config.heading);
+        assert!(Config::build(&args(&["minigrep", "--sort", "size", "x", "src"])).is_err());
+        assert!(Config::build(&args(&["minigrep", "x", "src", "--sort"])).is_err());
+    }
+
+    #[test]
+    fn build_reads_line_terminators(){
+        assert_eq!(Terminator::Line, Config::build(&args(&["minigrep", "x", "a.txt"])).unwrap().terminator);
+        assert_eq!(Terminator::Crlf, Config::build(&args(&["minigrep", "--crlf", "x", "a.txt"])).unwrap().terminator);
+        assert_eq!(Terminator::Nul, Config::build(&args(&["minigrep", "--null-data", "x", "files.list"])).unwrap().terminator);
+        assert!(Config::build(&args(&["minigrep", "--crlf", "--null-data", "x", "a.txt"])).is_err());
+        assert!(Config::build(&args(&["minigrep", "--follow", "--null-data", "x", "a.txt"])).is_err());
+    }
+
+    #[test]
+    fn build_rejects_bad_max_count(){
+        assert!(Config::build(&args(&["minigrep", "-m", "many", "error", "a.log"])).is_err());
+        assert!(Config::build(&args(&["minigrep", "error", "a.log", "--max-total"])).is_err());
+    }
+
+    #[test]
+    fn search_reader_stops_at_limit(){
+        // 第二个匹配之后是非法 UTF-8，如果继续往下读就会报错
+        let mut contents = b"duct one\nskip\nduct two\nduct three\n".to_vec();
+        contents.extend_from_slice(&[0xff, 0xfe, b'\n']);
+        let mut found = Vec::new();
+        let count = search_reader(&contents[..], Terminator::Line, Some(2), |line| line.contains("duct"), |n, line, _| found.push((n, line.to_string()))).unwrap();
+        assert_eq!(2, count);
+        assert_eq!(vec![(1, "duct one".to_string()), (3, "duct two".to_string())], found);
+    }
+
+    #[test]
+    fn build_rejects_unknown_option(){
+        assert!(Config::build(&args(&["minigrep", "--nope", "error", "app.log"])).is_err());
+    }
 }
@@ -5,12 +5,30 @@ use std::process;
 fn main() {
     let args: Vec<String> = env::args().collect();
 
+    if args.iter().skip(1).take_while(|arg| *arg != "--").any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", minigrep::USAGE);
+        return;
+    }
+
+    // --build-index [--index FILE] [PATH...]：只建立或更新索引，不搜索
+    // 用选项而不是子命令，搜 "index" 这个词的时候不会被当成建索引
+    let build_index = args.iter().skip(1).take_while(|arg| *arg != "--").position(|arg| arg == "--build-index");
+    if let Some(i) = build_index {
+        let mut index_args = args[1..].to_vec();
+        index_args.remove(i);
+        if let Err(e) = minigrep::index::run(&index_args) {
+            println!("Application error :{e}");
+            process::exit(1);
+        }
+        return;
+    }
+
     let config = Config::build(&args).unwrap_or_else(|err|{
         println!("Problem parsing arguments: {err}");
         process::exit(1);
     });
     println!("Search for {}",config.query);
-    println!("In file {}",config.file_path);
+    println!("In file {}",config.file_paths.join(", "));
     if let Err(e) =minigrep::run(config){
         println!("Application error :{e}");
         process::exit(1);
@@ -0,0 +1,266 @@
+// --normalize nfc|nfkc：比较前把 query 和每一行都做 Unicode 规范化
+// 这样预组合的 "é"（U+00E9）和 "e" + 组合重音符（U+0065 U+0301）能互相匹配
+// 需要位置时用 Normalized：规范化后的每个字节都记着它来自原文的哪个字符，匹配位置可以换算回原文
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+// 由 gen_normalize_table.py 生成，格式见脚本开头的说明
+static TABLE: &[u8] = include_bytes!("normalize.bin");
+
+// 朝鲜文音节的算法分解/组合常量（Unicode 标准第 3.12 节）
+const S_BASE: u32 = 0xAC00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11A7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const N_COUNT: u32 = V_COUNT * T_COUNT;
+const S_COUNT: u32 = L_COUNT * N_COUNT;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Form {
+    // 规范等价：只合并写法不同但含义相同的字符
+    Nfc,
+    // 兼容等价：另外把全角字母、连字 ﬁ、上标等折叠成普通字符
+    Nfkc,
+}
+
+impl Form {
+    pub fn from_label(label: &str) -> Option<Form> {
+        match label.to_ascii_lowercase().as_str() {
+            "nfc" => Some(Form::Nfc),
+            "nfkc" => Some(Form::Nfkc),
+            _ => None,
+        }
+    }
+}
+
+struct Tables {
+    canonical: HashMap<char, Vec<char>>,
+    compatibility: HashMap<char, Vec<char>>,
+    combining_class: HashMap<char, u8>,
+    composition: HashMap<(char, char), char>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = Tables {
+            canonical: HashMap::new(),
+            compatibility: HashMap::new(),
+            combining_class: HashMap::new(),
+            composition: HashMap::new(),
+        };
+        // 逐条读取记录，第一个字节是记录类型
+        let mut rest = TABLE;
+        while let Some(&kind) = rest.first() {
+            let record_len = match kind {
+                1 | 2 => {
+                    let len = rest[4] as usize;
+                    let chars = (0..len).map(|i| char_at(&rest[5 + 3 * i..])).collect();
+                    let map = if kind == 1 { &mut tables.canonical } else { &mut tables.compatibility };
+                    map.insert(char_at(&rest[1..]), chars);
+                    5 + 3 * len
+                }
+                3 => {
+                    tables.combining_class.insert(char_at(&rest[1..]), rest[4]);
+                    5
+                }
+                4 => {
+                    tables.composition.insert((char_at(&rest[1..]), char_at(&rest[4..])), char_at(&rest[7..]));
+                    10
+                }
+                _ => panic!("normalize.bin: unknown record type {kind} at byte {}", TABLE.len() - rest.len()),
+            };
+            rest = &rest[record_len..];
+        }
+        tables
+    })
+}
+
+fn char_at(bytes: &[u8]) -> char {
+    char::from_u32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])).expect("normalize.bin contains only valid code points")
+}
+
+fn combining_class(c: char) -> u8 {
+    tables().combining_class.get(&c).copied().unwrap_or(0)
+}
+
+// 只返回规范化后的文本，匹配时每一行都要调用；ASCII 行直接借用原文，不分配
+pub fn normalize(text: &str, form: Form) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(normalize_chars(text, form).into_iter().map(|(c, _)| c).collect())
+}
+
+pub struct Normalized {
+    pub text: String,
+    // text 的每个字节对应原文中的字符范围
+    spans: Vec<Range<usize>>,
+    original_len: usize,
+}
+
+impl Normalized {
+    // 规范化并记下位置对应关系，比 normalize 多一份和文本等长的位置表
+    pub fn new(text: &str, form: Form) -> Normalized {
+        let mut normalized = Normalized { text: String::new(), spans: Vec::new(), original_len: text.len() };
+        for (c, span) in normalize_chars(text, form) {
+            normalized.text.push(c);
+            normalized.spans.extend(std::iter::repeat_n(span, c.len_utf8()));
+        }
+        normalized
+    }
+
+    // 把规范化文本里的字节范围换算成原文里的字节范围
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.spans.get(range.start).map_or(self.original_len, |span| span.start);
+        if range.is_empty() {
+            return start..start;
+        }
+        start..self.spans[range.end - 1].end
+    }
+}
+
+// 在 line 里查找 needle（两者都先规范化），返回原文中的字节范围
+pub fn find(line: &str, needle: &str, form: Form) -> Option<Range<usize>> {
+    let haystack = Normalized::new(line, form);
+    let needle = normalize(needle, form);
+    let start = haystack.text.find(&*needle)?;
+    Some(haystack.original_range(start..start + needle.len()))
+}
+
+// 规范化后的字符，每个都带着它在原文中的字节范围；组合出来的字符覆盖参与组合的所有原文字符
+fn normalize_chars(text: &str, form: Form) -> Vec<(char, Range<usize>)> {
+    // 1. 完全分解
+    let mut chars: Vec<(char, Range<usize>)> = Vec::new();
+    for (start, c) in text.char_indices() {
+        let span = start..start + c.len_utf8();
+        decompose(c, form, |d| chars.push((d, span.clone())));
+    }
+
+    // 2. 规范排序：连续的非起始字符按组合类稳定排序
+    let mut i = 0;
+    while i < chars.len() {
+        let run = chars[i..].iter().take_while(|(c, _)| combining_class(*c) != 0).count();
+        if run > 1 {
+            chars[i..i + run].sort_by_key(|(c, _)| combining_class(*c));
+        }
+        i += run.max(1);
+    }
+
+    // 3. 规范组合：把未被阻断的字符合并到前面的起始字符上
+    let mut composed: Vec<(char, Range<usize>)> = Vec::new();
+    let mut starter: Option<usize> = None;
+    let mut last_class = 0;
+    for (c, span) in chars {
+        let class = combining_class(c);
+        if let Some(s) = starter {
+            let blocked = composed.len() - 1 != s && (last_class == 0 || last_class >= class);
+            if !blocked {
+                if let Some(combined) = compose(composed[s].0, c) {
+                    let merged = composed[s].1.start.min(span.start)..composed[s].1.end.max(span.end);
+                    composed[s] = (combined, merged);
+                    continue;
+                }
+            }
+        }
+        if class == 0 {
+            starter = Some(composed.len());
+        }
+        last_class = class;
+        composed.push((c, span));
+    }
+    composed
+}
+
+fn decompose(c: char, form: Form, mut push: impl FnMut(char)) {
+    let code = c as u32;
+    if (S_BASE..S_BASE + S_COUNT).contains(&code) {
+        let index = code - S_BASE;
+        push(char::from_u32(L_BASE + index / N_COUNT).unwrap());
+        push(char::from_u32(V_BASE + (index % N_COUNT) / T_COUNT).unwrap());
+        if !index.is_multiple_of(T_COUNT) {
+            push(char::from_u32(T_BASE + index % T_COUNT).unwrap());
+        }
+        return;
+    }
+    let tables = tables();
+    let mapping = match form {
+        Form::Nfkc => tables.compatibility.get(&c).or_else(|| tables.canonical.get(&c)),
+        Form::Nfc => tables.canonical.get(&c),
+    };
+    match mapping {
+        Some(chars) => chars.iter().copied().for_each(push),
+        None => push(c),
+    }
+}
+
+fn compose(a: char, b: char) -> Option<char> {
+    let (a, b) = (a as u32, b as u32);
+    if (L_BASE..L_BASE + L_COUNT).contains(&a) && (V_BASE..V_BASE + V_COUNT).contains(&b) {
+        return char::from_u32(S_BASE + ((a - L_BASE) * V_COUNT + (b - V_BASE)) * T_COUNT);
+    }
+    if (S_BASE..S_BASE + S_COUNT).contains(&a) && (a - S_BASE).is_multiple_of(T_COUNT) && (T_BASE + 1..T_BASE + T_COUNT).contains(&b) {
+        return char::from_u32(a + (b - T_BASE));
+    }
+    tables().composition.get(&(char::from_u32(a)?, char::from_u32(b)?)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code_points(text: &str) -> Vec<u32> {
+        text.chars().map(|c| c as u32).collect()
+    }
+
+    #[test]
+    fn composes_canonical_equivalents() {
+        assert_eq!("é", normalize("e\u{301}", Form::Nfc));
+        assert_eq!("Å", normalize("A\u{30A}", Form::Nfc));
+        // Å 埃米符号 U+212B 是单字符分解，规范化后变成 U+00C5
+        assert_eq!(vec![0xC5], code_points(&normalize("\u{212B}", Form::Nfc)));
+        // 组合符号乱序时先按组合类排序再组合
+        assert_eq!(vec![0x1E0D, 0x307], code_points(&normalize("d\u{307}\u{323}", Form::Nfc)));
+    }
+
+    #[test]
+    fn matches_python_unicodedata() {
+        // 期望值来自 unicodedata.normalize
+        assert_eq!(vec![0x1E33, 0x301], code_points(&normalize("\u{1E33}\u{301}", Form::Nfc)));
+        assert_eq!(vec![0x1E9B, 0x323], code_points(&normalize("\u{1E9B}\u{323}", Form::Nfc)));
+        assert_eq!(vec![0x1E69], code_points(&normalize("\u{1E9B}\u{323}", Form::Nfkc)));
+        assert_eq!(vec![0xFB01], code_points(&normalize("ﬁ", Form::Nfc)));
+        assert_eq!("fi1e", normalize("ﬁ①ｅ", Form::Nfkc));
+    }
+
+    #[test]
+    fn hangul_round_trips() {
+        assert_eq!("각", normalize("\u{1100}\u{1161}\u{11A8}", Form::Nfc));
+        assert_eq!("가", normalize("가", Form::Nfc));
+        assert_eq!("한국어", normalize("한국어", Form::Nfkc));
+    }
+
+    #[test]
+    fn reports_offsets_in_original_text() {
+        let line = "cafe\u{301} au lait";
+        assert_eq!(Some(0..6), find(line, "café", Form::Nfc));
+        assert_eq!(Some(7..9), find(line, "au", Form::Nfc));
+        // 前面的 e + U+0301 规范化后少了一个字节，后面的位置仍按原文算
+        assert_eq!(Some(4..10), find("e\u{301} needle", "needle", Form::Nfc));
+        assert_eq!(Some(0..3), find("e\u{301} needle", "é", Form::Nfc));
+        // 连字 ﬁ 整个字符对应 "fi"
+        assert_eq!(Some(3..6), find("ab ﬁx", "fi", Form::Nfkc));
+        assert_eq!(None, find(line, "cafe ", Form::Nfc));
+    }
+
+    #[test]
+    fn ascii_is_borrowed() {
+        assert!(matches!(normalize("plain ascii", Form::Nfkc), Cow::Borrowed("plain ascii")));
+        assert!(matches!(normalize("café", Form::Nfc), Cow::Owned(_)));
+    }
+}
@@ -0,0 +1,154 @@
+// --pre COMMAND：先用外部命令把文件转成文本（PDF、Office 文档等），再搜索命令的输出
+// 命令按空白拆分，文件路径作为最后一个参数，文件内容同时接到命令的标准输入
+// --pre-glob GLOB 限制哪些文件经过命令，可以给多次；不给时所有文件都经过
+use std::fs::File;
+use std::io;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preprocessor {
+    pub command: Vec<String>,
+    pub globs: Vec<String>,
+}
+
+impl Preprocessor {
+    pub fn new(command: &str) -> Option<Preprocessor> {
+        let command: Vec<String> = command.split_whitespace().map(String::from).collect();
+        if command.is_empty() {
+            return None;
+        }
+        Some(Preprocessor { command, globs: Vec::new() })
+    }
+
+    pub fn applies_to(&self, path: &str) -> bool {
+        // 不带 / 的 glob 只和文件名比较，*.pdf 可以匹配任意目录下的 PDF
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.globs.is_empty()
+            || self.globs.iter().any(|glob| glob_match(glob, if glob.contains('/') { path } else { name }))
+    }
+
+    // 返回命令的标准输出；命令启动失败或退出码非 0 时返回错误，错误信息带上 stderr 的第一行
+    pub fn run(&self, path: &str) -> io::Result<Vec<u8>> {
+        let output = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .arg(path)
+            .stdin(File::open(path)?)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot run {}: {e}", self.command[0])))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().next().unwrap_or("").trim();
+            let mut message = format!("{} exited with {}", self.command[0], output.status);
+            if !reason.is_empty() {
+                message = format!("{message}: {reason}");
+            }
+            return Err(io::Error::other(message));
+        }
+        Ok(output.stdout)
+    }
+}
+
+// 支持 *（任意个字符）、?（一个字符）和 [abc] / [a-z] / [!abc]
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // 回溯点：最近一个 * 在 pattern 里的位置，以及它当时对应的 text 位置
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => class_match(&pattern[p..], text[t]),
+            Some(&c) if c == text[t] => Some(1),
+            _ => None,
+        };
+        match (step, star) {
+            (Some(len), _) => {
+                p += len;
+                t += 1;
+            }
+            // 不匹配时让最近的 * 多吃一个字符再试
+            (None, Some((star_p, star_t))) => {
+                star = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// 匹配时返回 [...] 在 pattern 里占的长度；没有闭合的 [ 当普通字符
+fn class_match(pattern: &[char], c: char) -> Option<usize> {
+    let Some(close) = pattern.iter().skip(2).position(|&ch| ch == ']').map(|i| i + 2) else {
+        return (c == '[').then_some(1);
+    };
+    let (negate, body) = match pattern[1] {
+        '!' | '^' => (true, &pattern[2..close]),
+        _ => (false, &pattern[1..close]),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < body.len() {
+        if i + 2 < body.len() && body[i + 1] == '-' {
+            found |= (body[i]..=body[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= body[i] == c;
+            i += 1;
+        }
+    }
+    (found != negate).then_some(close + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_match("*.pdf", "report.pdf"));
+        assert!(!glob_match("*.pdf", "report.pdf.txt"));
+        assert!(glob_match("*.doc?", "a.docx"));
+        assert!(glob_match("data-[0-9][!a-z].*", "data-7X.csv"));
+        assert!(!glob_match("data-[0-9][!a-z].*", "data-7x.csv"));
+        assert!(glob_match("a*b*c", "aXXbYYbZc"));
+        assert!(glob_match("[]", "[]"));
+    }
+
+    #[test]
+    fn globs_without_slash_match_file_names() {
+        let mut pre = Preprocessor::new("cat").unwrap();
+        assert!(pre.applies_to("docs/a.txt"));
+        pre.globs = vec!["*.pdf".to_string(), "docs/*.odt".to_string()];
+        assert!(pre.applies_to("docs/2024/report.pdf"));
+        assert!(pre.applies_to("docs/notes.odt"));
+        assert!(!pre.applies_to("other/notes.odt"));
+        assert!(!pre.applies_to("docs/a.txt"));
+        assert!(Preprocessor::new("  ").is_none());
+    }
+
+    #[test]
+    fn runs_command_and_reports_failures() {
+        let path = std::env::temp_dir().join(format!("minigrep-pre-{}.txt", std::process::id()));
+        fs::write(&path, "safe\n").unwrap();
+        let path_str = path.to_str().unwrap();
+
+        assert_eq!(b"SAFE\n".to_vec(), Preprocessor::new("sed s/safe/SAFE/").unwrap().run(path_str).unwrap());
+
+        let err = Preprocessor::new("ls --no-such-flag").unwrap().run(path_str).unwrap_err();
+        assert!(err.to_string().starts_with("ls exited with"), "{err}");
+        let err = Preprocessor::new("minigrep-no-such-command").unwrap().run(path_str).unwrap_err();
+        assert!(err.to_string().starts_with("cannot run minigrep-no-such-command"), "{err}");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+// 布尔查询表达式：error AND timeout AND NOT retry、(a OR b) AND "quoted phrase"
+//
+// 语法（优先级从低到高）：
+//   or      := and ("OR" and)*
+//   and     := not ("AND" not)*
+//   not     := "NOT" not | primary
+//   primary := "(" or ")" | "带引号的短语" | 连续的普通单词
+//
+// 没有运算符的连续单词按原文合成一个短语，所以 "safe, fast" 这样的旧查询仍然是子串匹配
+// 引号里用 \" 表示引号、\\ 表示反斜杠；要原样搜索 AND、括号这些字符时用 -F 跳过解析
+use std::error::Error;
+use std::fmt;
+
+// 表达式树最多这么多层。解析、matches、map_terms 和 drop 都是递归的，
+// "((((...))))" 或几万个 NOT 这样的参数不能把栈撑爆
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term(String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    // 运算符后面或括号里缺少搜索词
+    ExpectedTerm { after: String, column: usize },
+    UnmatchedClose { column: usize },
+    // 两个搜索词之间没有 AND / OR，例如 a (b) 或 "x" y
+    MissingOperator { column: usize },
+    // 括号、NOT 或 AND/OR 链嵌套超过 MAX_DEPTH 层
+    TooDeep { column: usize },
+    UnclosedParen { column: usize },
+    UnclosedQuote { column: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::ExpectedTerm { after, column } => {
+                write!(f, "expected a search term after {after} at column {column}")
+            }
+            ParseError::UnmatchedClose { column } => write!(f, "unmatched ')' at column {column}"),
+            ParseError::MissingOperator { column } => write!(f, "expected AND or OR before column {column}"),
+            ParseError::TooDeep { column } => write!(f, "query is nested more than {MAX_DEPTH} levels deep at column {column}"),
+            ParseError::UnclosedParen { column } => write!(f, "'(' at column {column} is never closed"),
+            ParseError::UnclosedQuote { column } => write!(f, "quote at column {column} is never closed"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, ParseError> {
+        let tokens = tokenize(text)?;
+        if tokens.iter().all(|token| token.kind == TokenKind::Word) {
+            // 没有运算符、括号和引号（包括空查询）：整个 query 原样做子串匹配，首尾的空白也算在内
+            return Ok(Query::Term(text.to_string()));
+        }
+        let mut parser = Parser { text, tokens, pos: 0, nesting: 0 };
+        let (query, _) = parser.parse_or()?;
+        // parse_or 停下来时剩下的只可能是多余的 ')'，或者一个前面缺了运算符的搜索词
+        match parser.tokens.get(parser.pos) {
+            None => Ok(query),
+            Some(token) if token.kind == TokenKind::Close => Err(ParseError::UnmatchedClose { column: column(text, token.start) }),
+            Some(token) => Err(ParseError::MissingOperator { column: column(text, token.start) }),
+        }
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        match self {
+            Query::Term(term) => line.contains(term.as_str()),
+            Query::Not(inner) => !inner.matches(line),
+            Query::And(a, b) => a.matches(line) && b.matches(line),
+            Query::Or(a, b) => a.matches(line) || b.matches(line),
+        }
+    }
+
+    // 对每个搜索词做同样的变换，例如 Unicode 规范化
+    pub fn map_terms(self, f: &impl Fn(&str) -> String) -> Query {
+        match self {
+            Query::Term(term) => Query::Term(f(&term)),
+            Query::Not(inner) => Query::Not(Box::new(inner.map_terms(f))),
+            Query::And(a, b) => Query::And(Box::new(a.map_terms(f)), Box::new(b.map_terms(f))),
+            Query::Or(a, b) => Query::Or(Box::new(a.map_terms(f)), Box::new(b.map_terms(f))),
+        }
+    }
+
+    // 匹配行里一定会出现的词：只沿着 AND 往下找，OR 和 NOT 下面的词不一定出现
+    pub fn required_terms(&self) -> Vec<&str> {
+        match self {
+            Query::Term(term) => vec![term.as_str()],
+            Query::And(a, b) => {
+                let mut terms = a.required_terms();
+                terms.extend(b.required_terms());
+                terms
+            }
+            Query::Not(_) | Query::Or(_, _) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word,
+    Quoted(String),
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    // 在原文中的字节范围
+    start: usize,
+    end: usize,
+}
+
+// 报错时用的列号：从 1 开始按字符计数
+fn column(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count() + 1
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                let kind = if c == '(' { TokenKind::Open } else { TokenKind::Close };
+                tokens.push(Token { kind, start, end: start + 1 });
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                let end = loop {
+                    match chars.next() {
+                        None => return Err(ParseError::UnclosedQuote { column: column(text, start) }),
+                        Some((i, '"')) => break i + 1,
+                        // \" 和 \\ 转义，其他反斜杠原样保留
+                        Some((_, '\\')) if matches!(chars.peek(), Some((_, '"' | '\\'))) => {
+                            phrase.push(chars.next().unwrap().1);
+                        }
+                        Some((_, c)) => phrase.push(c),
+                    }
+                };
+                tokens.push(Token { kind: TokenKind::Quoted(phrase), start, end });
+            }
+            _ => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let kind = match &text[start..end] {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    _ => TokenKind::Word,
+                };
+                tokens.push(Token { kind, start, end });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    // 当前在几层括号和 NOT 里面，也就是解析函数递归了多少轮
+    nesting: usize,
+}
+
+// 下面的解析函数都同时返回子树的深度，AND/OR 链不递归也会让树变深
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|token| &token.kind)
+    }
+
+    fn parse_or(&mut self) -> Result<(Query, usize), ParseError> {
+        let (mut query, mut depth) = self.parse_and()?;
+        while self.peek() == Some(&TokenKind::Or) {
+            let op = self.pos;
+            self.pos += 1;
+            let (right, right_depth) = self.parse_and()?;
+            depth = self.deeper(depth.max(right_depth), op)?;
+            query = Query::Or(Box::new(query), Box::new(right));
+        }
+        Ok((query, depth))
+    }
+
+    fn parse_and(&mut self) -> Result<(Query, usize), ParseError> {
+        let (mut query, mut depth) = self.parse_not()?;
+        while self.peek() == Some(&TokenKind::And) {
+            let op = self.pos;
+            self.pos += 1;
+            let (right, right_depth) = self.parse_not()?;
+            depth = self.deeper(depth.max(right_depth), op)?;
+            query = Query::And(Box::new(query), Box::new(right));
+        }
+        Ok((query, depth))
+    }
+
+    fn parse_not(&mut self) -> Result<(Query, usize), ParseError> {
+        if self.peek() == Some(&TokenKind::Not) {
+            let op = self.pos;
+            self.pos += 1;
+            self.enter(op)?;
+            let (inner, depth) = self.parse_not()?;
+            self.nesting -= 1;
+            return Ok((Query::Not(Box::new(inner)), self.deeper(depth, op)?));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<(Query, usize), ParseError> {
+        let Some(token) = self.tokens.get(self.pos) else {
+            return Err(self.expected_term());
+        };
+        let start = token.start;
+        match &token.kind {
+            TokenKind::Open => {
+                self.enter(self.pos)?;
+                self.pos += 1;
+                let query = self.parse_or()?;
+                if self.peek() != Some(&TokenKind::Close) {
+                    return Err(ParseError::UnclosedParen { column: column(self.text, start) });
+                }
+                self.pos += 1;
+                self.nesting -= 1;
+                Ok(query)
+            }
+            TokenKind::Quoted(phrase) => {
+                let phrase = phrase.clone();
+                self.pos += 1;
+                Ok((Query::Term(phrase), 1))
+            }
+            TokenKind::Word => {
+                // 连续的普通单词连同中间的空白一起作为一个短语
+                let mut end = token.end;
+                self.pos += 1;
+                while let Some(Token { kind: TokenKind::Word, end: word_end, .. }) = self.tokens.get(self.pos) {
+                    end = *word_end;
+                    self.pos += 1;
+                }
+                Ok((Query::Term(self.text[start..end].to_string()), 1))
+            }
+            TokenKind::Close | TokenKind::And | TokenKind::Or | TokenKind::Not => Err(self.expected_term()),
+        }
+    }
+
+    // 进入一层括号或 NOT 之前检查，超过上限就不再往下递归
+    fn enter(&mut self, token: usize) -> Result<(), ParseError> {
+        self.nesting += 1;
+        if self.nesting > MAX_DEPTH {
+            return Err(self.too_deep(token));
+        }
+        Ok(())
+    }
+
+    // 在深度为 depth 的子树上再套一层运算符
+    fn deeper(&self, depth: usize, token: usize) -> Result<usize, ParseError> {
+        if depth >= MAX_DEPTH {
+            return Err(self.too_deep(token));
+        }
+        Ok(depth + 1)
+    }
+
+    fn too_deep(&self, token: usize) -> ParseError {
+        ParseError::TooDeep { column: column(self.text, self.tokens[token].start) }
+    }
+
+    // 当前位置需要一个搜索词，但遇到了别的东西或已到结尾
+    fn expected_term(&self) -> ParseError {
+        let (after, byte) = match self.pos.checked_sub(1).map(|i| &self.tokens[i]) {
+            Some(prev) => (format!("'{}'", &self.text[prev.start..prev.end]), prev.end),
+            None => ("start of query".to_string(), 0),
+        };
+        let byte = self.tokens.get(self.pos).map_or(byte, |token| token.start);
+        ParseError::ExpectedTerm { after, column: column(self.text, byte) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(text: &str) -> Box<Query> {
+        Box::new(Query::Term(text.to_string()))
+    }
+
+    #[test]
+    fn plain_queries_stay_substrings() {
+        assert_eq!(Query::Term("safe, fast".to_string()), Query::parse("safe, fast").unwrap());
+        assert_eq!(Query::Term(String::new()), Query::parse("").unwrap());
+        assert!(Query::parse("duct").unwrap().matches("safe, fast, productive."));
+        // 首尾的空白也是 query 的一部分
+        assert_eq!(Query::Term(" - ".to_string()), Query::parse(" - ").unwrap());
+        assert!(!Query::parse("a ").unwrap().matches("xa"));
+    }
+
+    #[test]
+    fn precedence_and_parentheses() {
+        assert_eq!(
+            Query::Or(term("a"), Box::new(Query::And(term("b"), Box::new(Query::Not(term("c")))))),
+            Query::parse("a OR b AND NOT c").unwrap()
+        );
+        assert_eq!(
+            Query::And(Box::new(Query::Or(term("a"), term("b"))), term("c d")),
+            Query::parse("(a OR b) AND c d").unwrap()
+        );
+    }
+
+    #[test]
+    fn quoted_phrases_keep_operators_literal() {
+        assert_eq!(Query::Term("rock AND roll".to_string()), Query::parse(r#""rock AND roll""#).unwrap());
+        assert_eq!(Query::Term(r#"say "hi""#.to_string()), Query::parse(r#""say \"hi\"""#).unwrap());
+    }
+
+    #[test]
+    fn evaluates_per_line() {
+        let query = Query::parse("error AND timeout AND NOT retry").unwrap();
+        assert!(query.matches("error: timeout after 30s"));
+        assert!(!query.matches("error: timeout, retry 1/3"));
+        assert!(!query.matches("timeout only"));
+        assert_eq!(vec!["error", "timeout"], query.required_terms());
+        assert!(Query::parse("a OR b").unwrap().required_terms().is_empty());
+    }
+
+    #[test]
+    fn map_terms_rewrites_every_term() {
+        let query = Query::parse("a AND NOT (b OR c)").unwrap().map_terms(&|term| term.to_uppercase());
+        assert_eq!(Query::parse("A AND NOT (B OR C)").unwrap(), query);
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        let error = |text| Query::parse(text).unwrap_err().to_string();
+        assert_eq!("expected a search term after 'AND' at column 10", error("error AND"));
+        assert_eq!("expected a search term after start of query at column 1", error("OR b"));
+        assert_eq!("expected a search term after '(' at column 2", error("()"));
+        assert_eq!("unmatched ')' at column 3", error("a ) b"));
+        assert_eq!("expected AND or OR before column 3", error("a (b)"));
+        assert_eq!("expected AND or OR before column 5", error(r#""a" b"#));
+        assert_eq!("expected AND or OR before column 10", error("(a OR b) NOT c"));
+        assert_eq!("'(' at column 1 is never closed", error("(a OR b"));
+        assert_eq!("quote at column 4 is never closed", error(r#"错误 "oops"#));
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Query::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(Err(ParseError::TooDeep { column: MAX_DEPTH + 1 }), Query::parse(&nested(MAX_DEPTH + 1)));
+        assert!(matches!(Query::parse(&nested(60_000)), Err(ParseError::TooDeep { .. })));
+        assert!(matches!(Query::parse(&format!("{}a", "NOT ".repeat(30_000))), Err(ParseError::TooDeep { .. })));
+
+        // 不递归的 AND 链也会让树变深
+        let chain = |terms: usize| vec!["a"; terms].join(" AND ");
+        assert!(Query::parse(&chain(MAX_DEPTH)).unwrap().matches("a"));
+        assert!(matches!(Query::parse(&chain(MAX_DEPTH + 1)), Err(ParseError::TooDeep { .. })));
+        assert!(matches!(Query::parse(&chain(50_000)), Err(ParseError::TooDeep { .. })));
+    }
+}
@@ -0,0 +1,101 @@
+// 按行尾把输入切成一条条记录，同时记下每条记录原来的行尾，输出时原样写回
+use std::io::{self, BufRead};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Terminator {
+    // 默认：\n 或 \r\n 结尾，匹配时两者都不算在行内
+    #[default]
+    Line,
+    // --crlf：只有 \r\n 结尾，单独的 \n 算行内容（比如 Excel 导出的 CSV 单元格里的换行）
+    Crlf,
+    // --null-data：以 NUL 结尾，配合 find -print0 这类输出，\n 算行内容
+    Nul,
+}
+
+impl Terminator {
+    // 最后一条记录没有行尾时，输出补上这个
+    pub fn default_ending(self) -> &'static str {
+        match self {
+            Terminator::Line => "\n",
+            Terminator::Crlf => "\r\n",
+            Terminator::Nul => "\0",
+        }
+    }
+}
+
+pub struct Records<R> {
+    reader: R,
+    terminator: Terminator,
+}
+
+pub fn records<R: BufRead>(reader: R, terminator: Terminator) -> Records<R> {
+    Records { reader, terminator }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    // (不含行尾的记录, 原来的行尾)
+    type Item = io::Result<(String, &'static str)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = Vec::new();
+        let delimiter = if self.terminator == Terminator::Nul { 0 } else { b'\n' };
+        loop {
+            match self.reader.read_until(delimiter, &mut buf) {
+                Ok(0) => break,
+                // --crlf 模式下前面不是 \r 的 \n 属于记录内容，接着读
+                Ok(_) if self.terminator == Terminator::Crlf && buf.ends_with(b"\n") && !buf.ends_with(b"\r\n") => continue,
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        if buf.is_empty() {
+            return None;
+        }
+        // 读到文件末尾、没有行尾的最后一条记录，行尾用默认值补上
+        let (ending, len) = match self.terminator {
+            Terminator::Line | Terminator::Crlf if buf.ends_with(b"\r\n") => ("\r\n", 2),
+            Terminator::Line if buf.ends_with(b"\n") => ("\n", 1),
+            Terminator::Nul if buf.ends_with(b"\0") => ("\0", 1),
+            terminator => (terminator.default_ending(), 0),
+        };
+        buf.truncate(buf.len() - len);
+        Some(String::from_utf8(buf).map(|line| (line, ending)).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(input: &str, terminator: Terminator) -> Vec<(String, &'static str)> {
+        records(input.as_bytes(), terminator).map(Result::unwrap).collect()
+    }
+
+    fn owned(expected: &[(&str, &'static str)]) -> Vec<(String, &'static str)> {
+        expected.iter().map(|&(line, ending)| (line.to_string(), ending)).collect()
+    }
+
+    #[test]
+    fn keeps_original_line_endings() {
+        assert_eq!(owned(&[("a", "\r\n"), ("b", "\n"), ("", "\n"), ("c\r", "\n")]), split("a\r\nb\n\nc\r", Terminator::Line));
+        assert_eq!(Vec::<(String, &str)>::new(), split("", Terminator::Line));
+    }
+
+    #[test]
+    fn crlf_mode_keeps_bare_newlines_inside_records() {
+        assert_eq!(owned(&[("id,note", "\r\n"), ("1,\"two\nlines\"", "\r\n"), ("2,x\n", "\r\n")]), split("id,note\r\n1,\"two\nlines\"\r\n2,x\n", Terminator::Crlf));
+    }
+
+    #[test]
+    fn null_data_splits_on_nul() {
+        assert_eq!(owned(&[("./a b\n", "\0"), ("./c\r\n", "\0"), ("tail", "\0")]), split("./a b\n\0./c\r\n\0tail", Terminator::Nul));
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        let mut records = records(&b"ok\n\xff\n"[..], Terminator::Line);
+        assert_eq!("ok", records.next().unwrap().unwrap().0);
+        assert_eq!(io::ErrorKind::InvalidData, records.next().unwrap().unwrap_err().kind());
+    }
+}
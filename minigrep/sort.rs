@@ -0,0 +1,102 @@
+// --sort / --sortr：搜索完再按文件排序输出，而不是按遍历顺序
+use std::fs;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Path,
+    Modified,
+    Created,
+    // 按文件里的匹配行数
+    Matches,
+}
+
+impl SortKey {
+    pub fn from_label(label: &str) -> Option<SortKey> {
+        match label {
+            "path" => Some(SortKey::Path),
+            "modified" => Some(SortKey::Modified),
+            "created" => Some(SortKey::Created),
+            "matches" => Some(SortKey::Matches),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sort {
+    pub key: SortKey,
+    // --sortr：倒序
+    pub reverse: bool,
+}
+
+// 一个文件里的全部匹配行：(行号, 行, 行尾)
+#[derive(Debug, PartialEq)]
+pub struct FileMatches {
+    pub path: String,
+    pub lines: Vec<(usize, String, &'static str)>,
+}
+
+// 默认都是升序（最早修改、匹配最少的在前），键相同时按路径排
+// 拿不到创建时间或修改时间的文件（有些文件系统不支持）排在最前面
+pub fn sort_groups(groups: &mut [FileMatches], sort: Sort) {
+    let time = |path: &str, created: bool| -> Option<SystemTime> {
+        let meta = fs::metadata(path).ok()?;
+        if created { meta.created().ok() } else { meta.modified().ok() }
+    };
+    match sort.key {
+        SortKey::Path => groups.sort_by(|a, b| a.path.cmp(&b.path)),
+        SortKey::Matches => groups.sort_by(|a, b| (a.lines.len(), &a.path).cmp(&(b.lines.len(), &b.path))),
+        SortKey::Modified | SortKey::Created => {
+            let created = sort.key == SortKey::Created;
+            groups.sort_by_cached_key(|group| (time(&group.path, created), group.path.clone()));
+        }
+    }
+    if sort.reverse {
+        groups.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    fn group(path: &str, matches: usize) -> FileMatches {
+        FileMatches { path: path.to_string(), lines: (1..=matches).map(|n| (n, String::new(), "\n")).collect() }
+    }
+
+    fn paths(groups: &[FileMatches]) -> Vec<&str> {
+        groups.iter().map(|group| group.path.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_by_path_and_matches() {
+        let mut groups = vec![group("b", 1), group("c", 3), group("a", 1)];
+        sort_groups(&mut groups, Sort { key: SortKey::Path, reverse: false });
+        assert_eq!(vec!["a", "b", "c"], paths(&groups));
+
+        sort_groups(&mut groups, Sort { key: SortKey::Matches, reverse: true });
+        assert_eq!(vec!["c", "b", "a"], paths(&groups));
+        sort_groups(&mut groups, Sort { key: SortKey::Matches, reverse: false });
+        assert_eq!(vec!["a", "b", "c"], paths(&groups));
+    }
+
+    #[test]
+    fn sorts_by_modification_time() {
+        let dir = std::env::temp_dir().join(format!("minigrep-sort-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut groups = Vec::new();
+        for (name, age) in [("old", 300), ("new", 0), ("mid", 100)] {
+            let path = dir.join(name);
+            let file = File::create(&path).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(age)).unwrap();
+            groups.push(group(path.to_str().unwrap(), 1));
+        }
+        sort_groups(&mut groups, Sort { key: SortKey::Modified, reverse: false });
+        let names: Vec<_> = paths(&groups).iter().map(|p| p.rsplit('/').next().unwrap()).collect();
+        assert_eq!(vec!["old", "mid", "new"], names);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
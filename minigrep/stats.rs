@@ -0,0 +1,84 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::time::Duration;
+
+// --stats：一次搜索的汇总计数
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+    pub files_scanned: usize,
+    // 被 --index 判定为不可能匹配、没有读取的文件
+    pub files_skipped: usize,
+    pub bytes_read: u64,
+    pub lines_matched: usize,
+    pub elapsed: Duration,
+    // --pre 命令失败、打不开或读到一半出错的文件，每条是 "路径: 原因"
+    pub failures: Vec<String>,
+}
+
+impl Stats {
+    // 记下一个文件的搜索结果：出错的文件也要算上出错前已经输出的匹配行
+    pub fn record(&mut self, path: &str, matched: usize, searched: io::Result<()>) {
+        self.lines_matched += matched;
+        match searched {
+            Ok(()) => self.files_scanned += 1,
+            Err(e) => self.failures.push(format!("{path}: {e}")),
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "files scanned: {}", self.files_scanned)?;
+        writeln!(f, "files skipped: {}", self.files_skipped)?;
+        writeln!(f, "bytes read:    {}", self.bytes_read)?;
+        writeln!(f, "lines matched: {}", self.lines_matched)?;
+        if !self.failures.is_empty() {
+            writeln!(f, "files failed:  {}", self.failures.len())?;
+        }
+        write!(f, "time taken:    {:.3}s", self.elapsed.as_secs_f64())
+    }
+}
+
+// 包一层 Read，把实际从文件读出的字节数累加到 bytes 上
+// 提前停止（-m）时没读到的部分不会被计入
+pub struct CountingReader<'a, R> {
+    inner: R,
+    bytes: &'a mut u64,
+}
+
+impl<'a, R: Read> CountingReader<'a, R> {
+    pub fn new(inner: R, bytes: &'a mut u64) -> CountingReader<'a, R> {
+        CountingReader { inner, bytes }
+    }
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        *self.bytes += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_bytes_passed_through() {
+        let mut bytes = 0;
+        let mut text = String::new();
+        CountingReader::new("héllo\n".as_bytes(), &mut bytes).read_to_string(&mut text).unwrap();
+        assert_eq!("héllo\n", text);
+        assert_eq!(7, bytes);
+    }
+
+    #[test]
+    fn summary_lists_every_counter() {
+        let stats = Stats { files_scanned: 2, files_skipped: 1, bytes_read: 10, lines_matched: 3, elapsed: Duration::from_millis(1500), failures: Vec::new() };
+        assert_eq!(
+            "files scanned: 2\nfiles skipped: 1\nbytes read:    10\nlines matched: 3\ntime taken:    1.500s",
+            stats.to_string()
+        );
+    }
+}
@@ -0,0 +1,388 @@
+// 端到端测试：在临时目录里准备好文件，直接运行编译出来的 minigrep，检查 stdout、stderr 和退出码
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POEM: &str = "I'm nobody! Who are you?\nAre you nobody, too?\nThen there's a pair of us - don't tell!\nThey'd banish us, you know.\n";
+
+// 每个测试一个独立的临时目录，测试结束时删除
+struct Fixture {
+    dir: PathBuf,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("minigrep-cli-{}-{n}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        Fixture { dir }
+    }
+
+    fn file(&self, name: &str, contents: impl AsRef<[u8]>) -> &Fixture {
+        let path = self.dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        self
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_minigrep"));
+        command.args(args).current_dir(&self.dir);
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Run {
+        Run::from(self.command(args).output().unwrap())
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+struct Run {
+    stdout: String,
+    stderr: String,
+    code: Option<i32>,
+}
+
+impl From<Output> for Run {
+    fn from(output: Output) -> Run {
+        Run {
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            code: output.status.code(),
+        }
+    }
+}
+
+impl Run {
+    // 等待会自己退出的子进程（--follow 用完额度），超时就杀掉它并让测试失败，不会一直挂着
+    fn wait(mut child: Child) -> Run {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while child.try_wait().unwrap().is_none() {
+            if Instant::now() > deadline {
+                child.kill().unwrap();
+                let run = Run::from(child.wait_with_output().unwrap());
+                panic!("minigrep did not exit within 5 seconds\nstdout: {}\nstderr: {}", run.stdout, run.stderr);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        Run::from(child.wait_with_output().unwrap())
+    }
+
+    // 去掉开头的 "Search for" / "In file" 两行，只留匹配结果
+    fn matches(&self) -> Vec<&str> {
+        assert_eq!(Some(0), self.code, "stdout: {}\nstderr: {}", self.stdout, self.stderr);
+        self.stdout.lines().skip(2).collect()
+    }
+}
+
+#[test]
+fn searches_a_single_file() {
+    let fixture = Fixture::new();
+    fixture.file("poem.txt", POEM);
+    let run = fixture.run(&["nobody", "poem.txt"]);
+    assert_eq!("Search for nobody\nIn file poem.txt\nI'm nobody! Who are you?\nAre you nobody, too?\n", run.stdout);
+    assert_eq!("", run.stderr);
+    assert_eq!(Some(0), run.code);
+}
+
+#[test]
+fn no_match_still_succeeds() {
+    let fixture = Fixture::new();
+    fixture.file("poem.txt", POEM);
+    assert!(fixture.run(&["somebody", "poem.txt"]).matches().is_empty());
+}
+
+#[test]
+fn prefixes_file_names_for_several_files_and_directories() {
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "duct tape\n").file("docs/b.txt", "productive\n").file("docs/.hidden", "duct\n");
+
+    let run = fixture.run(&["duct", "a.txt", "docs/b.txt"]);
+    assert_eq!(vec!["a.txt:duct tape", "docs/b.txt:productive"], run.matches());
+
+    // 目录递归展开、按路径排序，隐藏文件跳过
+    let run = fixture.run(&["duct", "."]);
+    assert_eq!(vec!["./a.txt:duct tape", "./docs/b.txt:productive"], run.matches());
+}
+
+#[test]
+fn limits_matches() {
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "x1\nx2\nx3\n").file("b.txt", "x4\nx5\n");
+    assert_eq!(vec!["a.txt:x1", "b.txt:x4"], fixture.run(&["-m", "1", "x", "a.txt", "b.txt"]).matches());
+    assert_eq!(vec!["a.txt:x1", "a.txt:x2", "a.txt:x3", "b.txt:x4"], fixture.run(&["--max-total", "4", "x", "a.txt", "b.txt"]).matches());
+}
+
+#[test]
+fn query_expressions_and_double_dash() {
+    let fixture = Fixture::new();
+    fixture.file("log.txt", "error: timeout\nerror: retry timeout\n-v flag\n");
+    assert_eq!(vec!["error: timeout"], fixture.run(&["error AND timeout AND NOT retry", "log.txt"]).matches());
+    assert_eq!(vec!["-v flag"], fixture.run(&["--", "-v", "log.txt"]).matches());
+
+    // 运算符和括号当普通文字搜：-F，或者放进引号里
+    fixture.file("main.rs", "// DO NOT EDIT\nfn main() {}\n");
+    assert_eq!(vec!["// DO NOT EDIT"], fixture.run(&["-F", "DO NOT", "main.rs"]).matches());
+    assert_eq!(vec!["fn main() {}"], fixture.run(&["--fixed-strings", "fn main()", "main.rs"]).matches());
+    assert_eq!(vec!["fn main() {}"], fixture.run(&["\"fn main()\"", "main.rs"]).matches());
+}
+
+#[test]
+fn help_documents_query_syntax() {
+    let fixture = Fixture::new();
+    let run = fixture.run(&["--help"]);
+    assert_eq!(Some(0), run.code);
+    assert!(run.stdout.starts_with("Usage: minigrep [OPTIONS] QUERY PATH...\n"), "{}", run.stdout);
+    assert!(run.stdout.contains(r#"write \" for a quote and \\ for a backslash"#), "{}", run.stdout);
+    assert!(run.stdout.contains("-F, --fixed-strings"), "{}", run.stdout);
+    // -- 之后的 -h 是搜索词
+    fixture.file("a.txt", "-h\n");
+    assert_eq!(vec!["-h"], fixture.run(&["--", "-h", "a.txt"]).matches());
+}
+
+#[test]
+fn output_keeps_input_line_endings() {
+    let fixture = Fixture::new();
+    fixture.file("dos.txt", "duct\r\nnone\r\nduct tape").file("unix.txt", "duct\n");
+    let run = fixture.run(&["duct", "dos.txt", "unix.txt"]);
+    assert_eq!("Search for duct\nIn file dos.txt, unix.txt\ndos.txt:duct\r\ndos.txt:duct tape\nunix.txt:duct\n", run.stdout);
+
+    // --crlf：单独的 \n 留在记录里，和记录一起输出
+    fixture.file("export.csv", "id,note\r\n1,\"duct\nline two\"\r\n2,other\r\n");
+    let run = fixture.run(&["--crlf", "line two", "export.csv"]);
+    assert_eq!("Search for line two\nIn file export.csv\n1,\"duct\nline two\"\r\n", run.stdout);
+}
+
+#[test]
+fn null_data_reads_and_writes_nul_terminated_records() {
+    let fixture = Fixture::new();
+    fixture.file("files.list", "./a.txt\0./docs/new\nline.txt\0./b.txt\0");
+    let run = fixture.run(&["--null-data", "txt", "files.list"]);
+    assert_eq!("Search for txt\nIn file files.list\n./a.txt\0./docs/new\nline.txt\0./b.txt\0", run.stdout);
+    let run = fixture.run(&["--null-data", "new", "files.list"]);
+    assert_eq!(vec!["./docs/new", "line.txt\0"], run.matches());
+}
+
+#[test]
+fn field_and_column_search() {
+    let fixture = Fixture::new();
+    fixture
+        .file("app.jsonl", "{\"level\":\"error\",\"msg\":\"disk\"}\n{\"level\":\"info\",\"msg\":\"error count 0\"}\n")
+        .file("data.csv", "name,city\n\"Smith, J\",Paris\nParis,Lyon\n");
+    assert_eq!(vec!["{\"level\":\"error\",\"msg\":\"disk\"}"], fixture.run(&["--field", "level=error", "app.jsonl"]).matches());
+    assert_eq!(vec!["\"Smith, J\",Paris"], fixture.run(&["--column", "2", "Paris", "data.csv"]).matches());
+}
+
+#[test]
+fn stats_go_to_stderr() {
+    let fixture = Fixture::new();
+    fixture.file("poem.txt", POEM);
+    let run = fixture.run(&["--stats", "nobody", "poem.txt"]);
+    assert_eq!(2, run.matches().len());
+    assert!(run.stderr.starts_with("files scanned: 1\nfiles skipped: 0\nbytes read:    114\nlines matched: 2\ntime taken:    "), "{}", run.stderr);
+}
+
+#[test]
+fn sort_and_heading() {
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "x\n").file("b.txt", "x\nx\nx\n").file("c.txt", "x\nx\n");
+    let run = fixture.run(&["--sortr", "matches", "--heading", "x", "."]);
+    assert_eq!(vec!["./b.txt", "x", "x", "x", "", "./c.txt", "x", "x", "", "./a.txt", "x"], run.matches());
+}
+
+#[test]
+fn build_index_and_index_search() {
+    let fixture = Fixture::new();
+    fixture.file("docs/a.txt", "safe, fast, productive.\n").file("docs/b.txt", "Pick three.\n");
+    let run = fixture.run(&["--build-index", "docs"]);
+    assert_eq!(Some(0), run.code, "{}", run.stdout);
+    assert!(fixture.path(".minigrep-index").exists());
+
+    let run = fixture.run(&["--index", ".minigrep-index", "--stats", "duct", "docs"]);
+    assert_eq!(vec!["docs/a.txt:safe, fast, productive."], run.matches());
+    assert!(run.stderr.contains("files skipped: 1\n"), "{}", run.stderr);
+
+    // 建索引时写成 ./docs，搜索时写成 docs，仍然用得上索引
+    let run = fixture.run(&["--build-index", "./docs"]);
+    assert_eq!(Some(0), run.code, "{}", run.stdout);
+    let run = fixture.run(&["--index", ".minigrep-index", "--stats", "duct", "docs"]);
+    assert!(run.stderr.contains("files skipped: 1\n"), "{}", run.stderr);
+
+    // 读不了的文件报出来，其余的照常建索引
+    let run = fixture.run(&["--build-index", "docs", "missing.txt"]);
+    assert_eq!(Some(1), run.code, "{}", run.stdout);
+    assert!(run.stderr.starts_with("minigrep: missing.txt: "), "{}", run.stderr);
+    assert!(run.stdout.contains("Indexed 0 files (2 unchanged, 0 removed)"), "{}", run.stdout);
+
+    // "index" 就是普通的搜索词
+    fixture.file("notes.txt", "rebuild the index\n");
+    assert_eq!(vec!["rebuild the index"], fixture.run(&["index", "notes.txt"]).matches());
+}
+
+#[test]
+fn decodes_and_normalizes_input() {
+    let fixture = Fixture::new();
+    // UTF-16LE 带 BOM 的 "café\n"，以及 GBK 编码的 "不是\n"
+    fixture.file("utf16.txt", b"\xff\xfec\x00a\x00f\x00\xe9\x00\n\x00").file("gbk.txt", b"\xb2\xbb\xca\xc7\n");
+    assert_eq!(vec!["café"], fixture.run(&["café", "utf16.txt"]).matches());
+    assert_eq!(vec!["不是"], fixture.run(&["--encoding", "gbk", "不是", "gbk.txt"]).matches());
+    assert_eq!(vec!["café"], fixture.run(&["--normalize", "nfc", "cafe\u{301}", "utf16.txt"]).matches());
+}
+
+#[test]
+fn searches_inside_archives() {
+    let fixture = Fixture::new();
+    // 用 zipfile 写的不压缩 zip，里面只有 in/a.txt = "duct tape\n"
+    fixture.file(
+        "a.zip",
+        b"PK\x03\x04\x14\x00\x00\x00\x00\x00\x00\x00!Ru\xd4;\xe3\n\x00\x00\x00\n\x00\x00\x00\x08\x00\x00\x00in/a.txtduct tape\n\
+PK\x01\x02\x14\x03\x14\x00\x00\x00\x00\x00\x00\x00!Ru\xd4;\xe3\n\x00\x00\x00\n\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x01\x00\x00\x00\x00in/a.txt\
+PK\x05\x06\x00\x00\x00\x00\x01\x00\x01\x006\x00\x00\x000\x00\x00\x00\x00\x00",
+    );
+    assert_eq!(vec!["a.zip!in/a.txt:duct tape"], fixture.run(&["duct", "a.zip"]).matches());
+}
+
+#[test]
+fn pre_command_output_is_searched_and_failures_are_reported() {
+    let fixture = Fixture::new();
+    fixture.file("a.doc", "tape\n").file("b.txt", "tape\n");
+    assert_eq!(vec!["./a.doc:duct"], fixture.run(&["--pre", "sed s/tape/duct/", "--pre-glob", "*.doc", "duct", "."]).matches());
+
+    let run = fixture.run(&["--pre", "false", "--pre-glob", "*.doc", "tape", "."]);
+    assert_eq!(Some(1), run.code);
+    assert_eq!("Search for tape\nIn file .\n./b.txt:tape\nApplication error :1 file(s) could not be searched\n", run.stdout);
+    assert_eq!("minigrep: ./a.doc: false exited with exit status: 1\n", run.stderr);
+}
+
+#[test]
+fn follow_prints_appended_lines_until_quota_is_used() {
+    let fixture = Fixture::new();
+    fixture.file("app.log", "error: first\ninfo\n");
+    let child = fixture.command(&["--follow", "-m", "2", "error", "app.log"]).stdout(Stdio::piped()).spawn().unwrap();
+    thread::sleep(Duration::from_millis(300));
+    let mut log = fs::OpenOptions::new().append(true).open(fixture.path("app.log")).unwrap();
+    log.write_all(b"error: second\nerror: third\n").unwrap();
+
+    // 额度用完后 --follow 自己退出
+    let run = Run::wait(child);
+    // 启动前就有的 "error: first" 不输出
+    assert_eq!(vec!["error: second", "error: third"], run.matches());
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_reports_a_broken_file_and_keeps_following_the_others() {
+    let fixture = Fixture::new();
+    fixture.file("a.log", "").file("b.log", "");
+    let child = fixture.command(&["--follow", "--max-total", "1", "error", "a.log", "b.log"]).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    thread::sleep(Duration::from_millis(300));
+    // b.log 换成同名目录，之后每次读它都会出错
+    fs::remove_file(fixture.path("b.log")).unwrap();
+    fs::create_dir(fixture.path("b.log")).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    let mut log = fs::OpenOptions::new().append(true).open(fixture.path("a.log")).unwrap();
+    log.write_all(b"error: still here\n").unwrap();
+
+    let run = Run::wait(child);
+    assert_eq!(vec!["a.log:error: still here"], run.matches());
+    // 同样的错误只报一次
+    assert_eq!(1, run.stderr.matches("minigrep: b.log: ").count(), "{}", run.stderr);
+}
+
+#[test]
+fn binary_files_are_skipped() {
+    let fixture = Fixture::new();
+    fixture.file("docs/bin.dat", b"\x80\x81duct\n").file("docs/nul.dat", b"duct\0\x01\n").file("docs/z.txt", "duct tape\n");
+    let run = fixture.run(&["--stats", "duct", "docs"]);
+    assert_eq!(vec!["docs/z.txt:duct tape"], run.matches());
+    assert!(run.stderr.starts_with("files scanned: 3\n"), "{}", run.stderr);
+}
+
+#[test]
+fn tui_refuses_to_start_without_a_terminal() {
+    let fixture = Fixture::new();
+    fixture.file("poem.txt", POEM);
+    let run = fixture.run(&["--tui", "nobody", "poem.txt"]);
+    assert_eq!(Some(1), run.code);
+    assert!(run.stdout.ends_with("Application error :--tui needs an interactive terminal\n"), "{}", run.stdout);
+}
+
+#[test]
+fn argument_errors_exit_with_status_1() {
+    let fixture = Fixture::new();
+    let cases: &[(&[&str], &str)] = &[
+        (&[], "not enough arguments"),
+        (&["nobody"], "not enough arguments"),
+        (&["-x", "nobody", "poem.txt"], "unknown option -x"),
+        (&["-m", "lots", "nobody", "poem.txt"], "-m requires a non-negative number"),
+        (&["--max-total"], "--max-total requires a non-negative number"),
+        (&["--field", "=error", "app.jsonl"], "--field requires a key"),
+        (&["--column", "0", "x", "a.csv"], "--column requires a column number starting at 1"),
+        (&["--encoding", "ebcdic", "x", "a.txt"], "unknown encoding ebcdic (expected utf-8, utf-16le, utf-16be, latin-1 or gbk)"),
+        (&["--normalize", "nfd", "x", "a.txt"], "unknown normalization form nfd (expected nfc or nfkc)"),
+        (&["--sort", "size", "x", "a.txt"], "unknown sort key size (expected path, modified, created or matches)"),
+        (&["--pre-glob", "*.pdf", "x", "a.txt"], "--pre-glob requires --pre"),
+        (&["--crlf", "--null-data", "x", "a.txt"], "--crlf and --null-data cannot be used together"),
+        (&["--follow", "--encoding", "utf-16le", "x", "a.txt"], "--follow does not support UTF-16 input"),
+        (&["error AND", "a.txt"], "invalid query: expected a search term after 'AND' at column 10"),
+        (&["fn main()", "a.rs"], "invalid query: expected AND or OR before column 8"),
+    ];
+    for (args, message) in cases {
+        let run = fixture.run(args);
+        assert_eq!(Some(1), run.code, "{args:?}");
+        assert_eq!(format!("Problem parsing arguments: {message}\n"), run.stdout, "{args:?}");
+    }
+
+    // 嵌套很深的查询要正常报错退出，不能爆栈
+    let nested = format!("{}a{}", "(".repeat(60_000), ")".repeat(60_000));
+    let not_chain = format!("{}a", "NOT ".repeat(30_000));
+    for query in [&nested, &not_chain] {
+        let run = fixture.run(&[query, "a.txt"]);
+        assert_eq!(Some(1), run.code, "{}", run.stderr);
+        assert!(run.stdout.starts_with("Problem parsing arguments: invalid query: query is nested more than 256 levels deep at column "), "{}", run.stdout);
+    }
+}
+
+#[test]
+fn missing_files_are_reported_and_the_search_goes_on() {
+    let fixture = Fixture::new();
+    fixture.file("poem.txt", POEM);
+    let run = fixture.run(&["nobody", "missing.txt", "poem.txt"]);
+    assert_eq!(Some(1), run.code);
+    assert_eq!(
+        "Search for nobody\nIn file missing.txt, poem.txt\npoem.txt:I'm nobody! Who are you?\npoem.txt:Are you nobody, too?\n\
+Application error :1 file(s) could not be searched\n",
+        run.stdout
+    );
+    assert_eq!("minigrep: missing.txt: No such file or directory (os error 2)\n", run.stderr);
+
+    // 索引文件不存在时当作没有索引，但内容不对要报错
+    assert_eq!(2, fixture.run(&["--index", "missing-index", "nobody", "poem.txt"]).matches().len());
+    fixture.file("bad-index", "not an index");
+    let run = fixture.run(&["--index", "bad-index", "nobody", "poem.txt"]);
+    assert_eq!(Some(1), run.code);
+    assert!(run.stdout.ends_with("Application error :not a minigrep index file\n"), "{}", run.stdout);
+}
+
+#[test]
+fn build_index_rejects_bad_arguments() {
+    let fixture = Fixture::new();
+    let run = fixture.run(&["--build-index", "--index"]);
+    assert_eq!(Some(1), run.code);
+    assert!(run.stdout.starts_with("Application error :"), "{}", run.stdout);
+    assert!(!Path::new(&fixture.path(".minigrep-index")).exists());
+}
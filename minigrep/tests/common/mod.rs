@@ -0,0 +1,50 @@
+// 属性测试和随机冒烟测试共用的随机数工具，只用标准库
+// MINIGREP_SEED 固定种子复现失败，MINIGREP_ITERATIONS 调大每个测试的轮数
+#![allow(dead_code)]
+
+use std::env;
+
+// xorshift64*：不追求统计质量，只要快、可复现
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new() -> Rng {
+        let seed = env::var("MINIGREP_SEED").ok().and_then(|s| s.parse().ok()).unwrap_or(0x9E37_79B9_7F4A_7C15);
+        Rng(seed | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // 0..n 之间的随机数
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    // 从 pieces 里随机拼出最多 max 段
+    pub fn string(&mut self, max: usize, pieces: &[&str]) -> String {
+        let len = self.below(max + 1);
+        (0..len).map(|_| *self.pick(pieces)).collect()
+    }
+
+    pub fn bytes(&mut self, max: usize) -> Vec<u8> {
+        let len = self.below(max + 1);
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+pub fn iterations(default: usize) -> usize {
+    env::var("MINIGREP_ITERATIONS").ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+}
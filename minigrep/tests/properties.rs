@@ -0,0 +1,152 @@
+// 属性测试：随机生成输入，把 search / search_reader / Config::is_match 的结果和一个最朴素的实现对比
+mod common;
+
+use common::{iterations, Rng};
+use minigrep::query::Query;
+use minigrep::record::Terminator;
+use minigrep::{search, search_reader, Config};
+
+// 故意多放空白、\r、\n 和非 ASCII 字符，边界情况才容易被随机到
+const TEXT: &[&str] = &["a", "b", "ab", " ", "\t", "\r", "\n", "\r\n", "é", "e\u{301}", "-", "\""];
+const QUERY: &[&str] = &["a", "b", " ", "\t", "\r", "é", "-"];
+
+// 行的定义：以 \n 或 \r\n 结尾，最后一行可以没有结尾；单独的 \r 是行内容
+fn oracle_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    let last = lines.pop().unwrap();
+    for line in &mut lines {
+        *line = line.strip_suffix('\r').unwrap_or(line);
+    }
+    if !last.is_empty() {
+        lines.push(last);
+    }
+    lines
+}
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn search_matches_oracle() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(20_000) {
+        let text = rng.string(12, TEXT);
+        let query = rng.string(3, QUERY);
+        let expected: Vec<&str> = oracle_lines(&text).into_iter().filter(|line| line.contains(&query)).collect();
+        assert_eq!(expected, search(&query, &text), "query {query:?} in {text:?}");
+    }
+}
+
+#[test]
+fn search_reader_agrees_with_search() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(20_000) {
+        let text = rng.string(12, TEXT);
+        let query = rng.string(3, QUERY);
+        let limit = if rng.chance(30) { Some(rng.below(3)) } else { None };
+
+        let expected: Vec<(usize, &str)> = oracle_lines(&text)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, line)| line.contains(&query))
+            .map(|(i, line)| (i + 1, line))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        let mut found = Vec::new();
+        let count = search_reader(text.as_bytes(), Terminator::Line, limit, |line| line.contains(&query), |n, line, _| {
+            found.push((n, line.to_string()))
+        })
+        .unwrap();
+        let found: Vec<(usize, &str)> = found.iter().map(|(n, line)| (*n, line.as_str())).collect();
+        assert_eq!(expected, found, "query {query:?} in {text:?} with limit {limit:?}");
+        assert_eq!(expected.len(), count);
+    }
+}
+
+#[test]
+fn records_and_endings_reproduce_the_input() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(20_000) {
+        let text = rng.string(12, &["a", "\r", "\n", "\r\n", "\0", "é"]);
+        for terminator in [Terminator::Line, Terminator::Crlf, Terminator::Nul] {
+            let mut output = String::new();
+            search_reader(text.as_bytes(), terminator, None, |_| true, |_, line, ending| {
+                output.push_str(line);
+                output.push_str(ending);
+            })
+            .unwrap();
+            // 只有最后一条没有行尾的记录会被补上默认行尾
+            let expected = if output.len() > text.len() { format!("{text}{}", terminator.default_ending()) } else { text.clone() };
+            assert_eq!(expected, output, "{text:?} with {terminator:?}");
+        }
+    }
+}
+
+#[test]
+fn plain_queries_are_substring_matches() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(20_000) {
+        let query = rng.string(4, QUERY);
+        let line = rng.string(8, &TEXT[..TEXT.len() - 3]);
+        // -- 让以 - 开头的 query 也当作普通参数
+        let config = Config::build(&args(&["minigrep", "--", &query, "a.txt"])).unwrap();
+        assert_eq!(line.contains(&query), config.is_match(&line), "query {query:?} on {line:?}");
+    }
+}
+
+// 随机表达式树，渲染成字符串后解析，结果要和直接对树求值一致
+#[derive(Debug)]
+enum Expr {
+    Term(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn random(rng: &mut Rng, depth: usize) -> Expr {
+        let choice = if depth == 0 { 0 } else { rng.below(4) };
+        match choice {
+            0 => {
+                let term = rng.string(2, &["a", "b", "c", " "]);
+                if term.trim().is_empty() { Expr::Term("a".to_string()) } else { Expr::Term(term) }
+            }
+            1 => Expr::Not(Box::new(Expr::random(rng, depth - 1))),
+            2 => Expr::And(Box::new(Expr::random(rng, depth - 1)), Box::new(Expr::random(rng, depth - 1))),
+            _ => Expr::Or(Box::new(Expr::random(rng, depth - 1)), Box::new(Expr::random(rng, depth - 1))),
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Expr::Term(term) => format!("\"{term}\""),
+            Expr::Not(inner) => format!("NOT {}", inner.render()),
+            Expr::And(a, b) => format!("({} AND {})", a.render(), b.render()),
+            Expr::Or(a, b) => format!("({} OR {})", a.render(), b.render()),
+        }
+    }
+
+    fn eval(&self, line: &str) -> bool {
+        match self {
+            Expr::Term(term) => line.contains(term.as_str()),
+            Expr::Not(inner) => !inner.eval(line),
+            Expr::And(a, b) => a.eval(line) && b.eval(line),
+            Expr::Or(a, b) => a.eval(line) || b.eval(line),
+        }
+    }
+}
+
+#[test]
+fn parsed_expressions_evaluate_like_the_tree() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(5_000) {
+        let expr = Expr::random(&mut rng, 4);
+        let text = expr.render();
+        let query = Query::parse(&text).unwrap_or_else(|e| panic!("{text:?} failed to parse: {e}"));
+        for _ in 0..10 {
+            let line = rng.string(8, &["a", "b", "c", " "]);
+            assert_eq!(expr.eval(&line), query.matches(&line), "{text} on {line:?}");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// 随机冒烟测试：用固定种子的随机参数和随机数据跑参数解析、查询解析、匹配和解码，只要求不 panic、错误能正常返回
+// 不是覆盖率引导的模糊测试，只是把随机输入多跑几轮；跑得更久：MINIGREP_ITERATIONS=1000000 MINIGREP_SEED=42 cargo test --test smoke
+mod common;
+
+use common::{iterations, Rng};
+use minigrep::encoding::{self, Encoding};
+use minigrep::field::Field;
+use minigrep::inflate;
+use minigrep::query::Query;
+use minigrep::record::Terminator;
+use minigrep::{search_reader, Config};
+use std::io::ErrorKind;
+
+const ARGS: &[&str] = &[
+    "-F", "--follow", "--stats", "--crlf", "--null-data", "--tui", "--heading", "--sort", "--sortr", "path", "matches", "-m", "--max-count", "--max-total",
+    "--field", "--column", "--index", "--encoding", "gbk", "utf-16le", "--normalize", "nfc", "nfkc", "--pre", "--pre-glob",
+    "--", "-", "-x", "0", "1", "-1", "99999999999999999999999", "=", "a=b", "level=", "AND", "OR", "NOT", "(", ")", "\"",
+    "a", "é", "", " ", "a.txt",
+];
+
+const QUERY: &[&str] = &["a", "b", " ", "AND", "OR", "NOT", "(", ")", "\"", "\\", "é", "中", "\t", "-"];
+
+#[test]
+fn config_build_and_is_match_never_panic() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(20_000) {
+        let mut list = vec!["minigrep".to_string()];
+        for _ in 0..rng.below(7) {
+            list.push(if rng.chance(20) { rng.string(4, QUERY) } else { rng.pick(ARGS).to_string() });
+        }
+        let Ok(config) = Config::build(&list) else {
+            continue;
+        };
+        assert!(!config.file_paths.is_empty(), "{list:?}");
+        let _ = config.query_trigrams();
+        let _ = config.remaining(rng.below(3), rng.below(3));
+        for _ in 0..5 {
+            let line = rng.string(8, &["a", "b", " ", "é", "e\u{301}", "{\"level\":\"a\"}", ",", "\"", "\r"]);
+            let _ = config.is_match(&line);
+        }
+    }
+}
+
+#[test]
+fn query_parse_errors_point_inside_the_query() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(20_000) {
+        let text = rng.string(10, QUERY);
+        match Query::parse(&text) {
+            Ok(query) => {
+                let _ = query.matches(&rng.string(6, QUERY));
+                let _ = query.required_terms();
+            }
+            Err(e) => {
+                // 列号从 1 开始，最多指到末尾后面一个字符
+                let message = e.to_string();
+                let column: usize = message.split("column ").nth(1).and_then(|rest| rest.split(' ').next()).unwrap().parse().unwrap();
+                assert!((1..=text.chars().count() + 1).contains(&column), "{text:?}: {message}");
+            }
+        }
+    }
+}
+
+#[test]
+fn decoding_and_searching_random_bytes_never_panics() {
+    let mut rng = Rng::new();
+    let encodings = [None, Some(Encoding::Utf8), Some(Encoding::Utf16Le), Some(Encoding::Utf16Be), Some(Encoding::Latin1), Some(Encoding::Gbk)];
+    for _ in 0..iterations(5_000) {
+        let mut bytes = rng.bytes(40);
+        if rng.chance(20) {
+            // 偶尔带上 BOM
+            bytes.splice(0..0, rng.pick(&[&b"\xef\xbb\xbf"[..], b"\xff\xfe", b"\xfe\xff"]).iter().copied());
+        }
+        let encoding = *rng.pick(&encodings);
+        let reader = encoding::decode(&bytes[..], encoding).unwrap();
+        // 不合法的 UTF-8 只能以 InvalidData 的形式报出来
+        let terminator = *rng.pick(&[Terminator::Line, Terminator::Crlf, Terminator::Nul]);
+        if let Err(e) = search_reader(reader, terminator, None, |line| line.contains('a'), |_, _, _| {}) {
+            assert_eq!(ErrorKind::InvalidData, e.kind(), "{bytes:?} as {encoding:?}: {e}");
+        }
+        let _ = encoding::decode_all(&bytes, encoding);
+    }
+}
+
+#[test]
+fn field_extraction_never_panics() {
+    let mut rng = Rng::new();
+    let pieces = ["{", "}", "[", "]", "\"", "\\", "u", "d83d", ":", ",", "a", "1", "-", ".", "e", "true", "null", " "];
+    let fields = [Field::json("a"), Field::json("a.b"), Field::Csv(1), Field::Csv(3)];
+    for _ in 0..iterations(20_000) {
+        let line = rng.string(12, &pieces);
+        for field in &fields {
+            let _ = field.extract(&line);
+        }
+    }
+}
+
+#[test]
+fn inflate_rejects_garbage_without_panicking() {
+    let mut rng = Rng::new();
+    for _ in 0..iterations(5_000) {
+        let mut bytes = rng.bytes(64);
+        if rng.chance(50) {
+            bytes.splice(0..0, [0x1f, 0x8b, 0x08, 0x00, 0, 0, 0, 0, 0, 0x03]);
+        }
+        let _ = inflate::inflate(&bytes, 1 << 20);
+        let _ = inflate::gunzip(&bytes, 1 << 20);
+    }
+}
@@ -0,0 +1,528 @@
+// --tui：交互式结果浏览器
+// 只用标准库：终端的 raw 模式交给 stty 切换，画面用 ANSI 转义序列绘制
+use std::env;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+use crate::{archive, encoding, record, search_files, Config};
+
+// 预览区在选中行上下各显示几行
+const CONTEXT: usize = 3;
+const PAGE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Enter,
+    Backspace,
+    Esc,
+    CtrlC,
+}
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Browse,
+    // 正在编辑 query，每输入一个字符就重新搜索
+    Query,
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Continue,
+    Quit,
+    Open(usize),
+}
+
+// 选中结果周围的几行：换选中项或重新搜索之前一直复用，重画屏幕时不再读文件、跑 --pre
+struct Preview {
+    hit: usize,
+    // 第一行的行号
+    first: usize,
+    // 读不出来时是 None
+    lines: Option<Vec<String>>,
+}
+
+struct Browser {
+    config: Config,
+    hits: Vec<Hit>,
+    selected: usize,
+    mode: Mode,
+    message: Option<String>,
+    preview: Option<Preview>,
+}
+
+impl Browser {
+    fn new(config: Config) -> Browser {
+        let mut browser = Browser { config, hits: Vec::new(), selected: 0, mode: Mode::Browse, message: None, preview: None };
+        browser.refresh();
+        browser
+    }
+
+    fn refresh(&mut self) {
+        self.hits.clear();
+        self.selected = 0;
+        self.message = None;
+        self.preview = None;
+        // 空 query 会匹配所有行，编辑途中先不搜
+        if self.config.query.is_empty() {
+            return;
+        }
+        let mut hits = Vec::new();
+        let result = search_files(&self.config, |path, line_number, line, _| {
+            hits.push(Hit { path: path.to_string(), line_number, line: line.to_string() });
+        });
+        self.hits = hits;
+        match result {
+            Ok(stats) if !stats.failures.is_empty() => {
+                self.message = Some(format!("{} file(s) could not be searched: {}", stats.failures.len(), stats.failures[0]));
+            }
+            Ok(_) => {}
+            Err(e) => self.message = Some(format!("search failed: {e}")),
+        }
+    }
+
+    fn handle(&mut self, key: Key) -> Action {
+        if key == Key::CtrlC {
+            return Action::Quit;
+        }
+        match self.mode {
+            Mode::Browse => match key {
+                Key::Char('q') => return Action::Quit,
+                Key::Char('j') | Key::Down => self.move_by(1),
+                Key::Char('k') | Key::Up => self.move_by(-1),
+                Key::PageDown => self.move_by(PAGE as isize),
+                Key::PageUp => self.move_by(-(PAGE as isize)),
+                Key::Char('/') => self.mode = Mode::Query,
+                Key::Enter if !self.hits.is_empty() => return Action::Open(self.selected),
+                _ => {}
+            },
+            Mode::Query => match key {
+                Key::Char(c) => {
+                    let mut query = self.config.query.clone();
+                    query.push(c);
+                    self.edit_query(query);
+                }
+                Key::Backspace => {
+                    let mut query = self.config.query.clone();
+                    query.pop();
+                    self.edit_query(query);
+                }
+                Key::Enter | Key::Esc => self.mode = Mode::Browse,
+                _ => {}
+            },
+        }
+        Action::Continue
+    }
+
+    // 输入到一半的表达式（比如 "a AND"）解析不了，先保留文字、提示错误，结果不变
+    fn edit_query(&mut self, query: String) {
+        match self.config.set_query(query.clone()) {
+            Ok(()) => self.refresh(),
+            Err(e) => {
+                self.config.query = query;
+                self.message = Some(format!("invalid query: {e}"));
+            }
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.hits.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    // 生成整屏内容：标题、按文件分组的结果列表、预览区、状态栏
+    fn render(&mut self, rows: usize, cols: usize) -> String {
+        let preview_height = 2 * CONTEXT + 2;
+        let list_height = rows.saturating_sub(preview_height + 2).max(1);
+        let mut screen = Vec::new();
+
+        let files = {
+            let mut paths: Vec<&str> = self.hits.iter().map(|hit| hit.path.as_str()).collect();
+            paths.dedup();
+            paths.len()
+        };
+        let cursor = if self.mode == Mode::Query { "_" } else { "" };
+        screen.push(fit(
+            &format!("query: {}{cursor}   {} matches in {files} files", self.config.query, self.hits.len()),
+            cols,
+        ));
+
+        // 文件名单独占一行，后面跟着它的匹配行
+        let mut list: Vec<(Option<usize>, String)> = Vec::new();
+        for (i, hit) in self.hits.iter().enumerate() {
+            if i == 0 || self.hits[i - 1].path != hit.path {
+                list.push((None, format!("\x1b[1m{}\x1b[0m", fit(&hit.path, cols))));
+            }
+            list.push((Some(i), fit(&format!("  {:>5}: {}", hit.line_number, hit.line), cols)));
+        }
+        let selected_row = list.iter().position(|(hit, _)| *hit == Some(self.selected)).unwrap_or(0);
+        let start = (selected_row + 1).saturating_sub(list_height);
+        for (hit, text) in list.iter().skip(start).take(list_height) {
+            if *hit == Some(self.selected) {
+                screen.push(format!("\x1b[7m{text}\x1b[0m"));
+            } else {
+                screen.push(text.clone());
+            }
+        }
+        screen.resize(list_height + 1, String::new());
+
+        screen.push("─".repeat(cols));
+        screen.extend(self.preview(cols));
+        screen.resize(list_height + preview_height + 1, String::new());
+
+        let status = match (&self.message, &self.mode) {
+            (Some(message), _) => message.clone(),
+            (None, Mode::Query) => "type to refine   Enter/Esc done".to_string(),
+            (None, Mode::Browse) => "j/k move   / refine   Enter open in $EDITOR   q quit".to_string(),
+        };
+        screen.push(fit(&status, cols));
+
+        let mut out = String::from("\x1b[H");
+        for line in screen {
+            out.push_str(&line);
+            out.push_str("\x1b[K\r\n");
+        }
+        out.push_str("\x1b[J");
+        out
+    }
+
+    fn preview(&mut self, cols: usize) -> Vec<String> {
+        if self.selected >= self.hits.len() {
+            return Vec::new();
+        }
+        if self.preview.as_ref().is_none_or(|preview| preview.hit != self.selected) {
+            self.preview = Some(self.load_preview(self.selected));
+        }
+        let hit = &self.hits[self.selected];
+        let Some(preview) = &self.preview else {
+            return Vec::new();
+        };
+        let Some(lines) = &preview.lines else {
+            return vec![format!("cannot read {}", hit.path)];
+        };
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let line_number = preview.first + i;
+                let text = fit(&format!("{line_number:>5}: {line}"), cols);
+                if line_number == hit.line_number {
+                    format!("\x1b[1m{text}\x1b[0m")
+                } else {
+                    text
+                }
+            })
+            .collect()
+    }
+
+    fn load_preview(&self, index: usize) -> Preview {
+        let hit = &self.hits[index];
+        let first = hit.line_number.saturating_sub(CONTEXT).max(1);
+        // 经过 --pre 的文件预览命令输出，行号才对得上；archive.zip!inner.txt 这种结果从压缩包里取内容
+        let bytes = match self.config.pre.as_ref().filter(|pre| pre.applies_to(&hit.path)) {
+            Some(pre) => pre.run(&hit.path),
+            None => fs::read(&hit.path).or_else(|e| archive::read_member(&hit.path).ok_or(e)),
+        };
+        // 和搜索时一样按 --crlf / --null-data 切分记录，行号才一致
+        let lines = bytes.and_then(|bytes| encoding::decode_all(&bytes, self.config.encoding)).ok().map(|contents| {
+            let contents = String::from_utf8_lossy(&contents);
+            record::records(contents.as_bytes(), self.config.terminator)
+                .skip(first - 1)
+                .take(hit.line_number + CONTEXT + 1 - first)
+                .map_while(Result::ok)
+                .map(|(line, _)| line)
+                .collect()
+        });
+        Preview { hit: index, first, lines }
+    }
+}
+
+// 按显示宽度截断：中日韩等全角字符占两列，制表符等控制字符换成空格
+fn fit(text: &str, cols: usize) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let c = if c.is_control() { ' ' } else { c };
+        let w = if is_wide(c) { 2 } else { 1 };
+        if width + w > cols {
+            break;
+        }
+        width += w;
+        out.push(c);
+    }
+    out
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1FAFF | 0x20000..=0x3FFFD)
+}
+
+// 从终端读一个按键；超时没有输入时返回 None
+// 终端设置为 min 0 time 1，所以单独按 Esc 和方向键的转义序列可以区分开
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(first) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match first {
+        0x03 => Key::CtrlC,
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => match read_byte(input)? {
+            Some(b'[') => match read_byte(input)? {
+                Some(b'A') => Key::Up,
+                Some(b'B') => Key::Down,
+                Some(b'5') => {
+                    read_byte(input)?;
+                    Key::PageUp
+                }
+                Some(b'6') => {
+                    read_byte(input)?;
+                    Key::PageDown
+                }
+                _ => Key::Esc,
+            },
+            _ => Key::Esc,
+        },
+        b if b < 0x80 => Key::Char(b as char),
+        // UTF-8 多字节字符：按首字节确定后面还有几个字节
+        b => {
+            let len = if b >= 0xF0 { 4 } else if b >= 0xE0 { 3 } else { 2 };
+            let mut bytes = vec![b];
+            for _ in 1..len {
+                bytes.extend(read_byte(input)?);
+            }
+            match std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()) {
+                Some(c) => Key::Char(c),
+                None => return Ok(None),
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut buf = [0; 1];
+    Ok((input.read(&mut buf)? == 1).then_some(buf[0]))
+}
+
+struct Terminal {
+    tty: File,
+    saved: String,
+}
+
+impl Terminal {
+    fn enter() -> io::Result<Terminal> {
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let saved = stty(&["-g"])?;
+        let mut terminal = Terminal { tty, saved };
+        terminal.resume()?;
+        Ok(terminal)
+    }
+
+    // 切到备用屏幕并进入 raw 模式
+    fn resume(&mut self) -> io::Result<()> {
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        self.tty.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        self.tty.flush()
+    }
+
+    // 临时恢复正常终端，例如打开编辑器之前
+    fn suspend(&mut self) -> io::Result<()> {
+        self.tty.write_all(b"\x1b[?25h\x1b[?1049l")?;
+        self.tty.flush()?;
+        stty(&[&self.saved]).map(|_| ())
+    }
+
+    fn size(&self) -> (usize, usize) {
+        stty(&["size"])
+            .ok()
+            .and_then(|size| {
+                let (rows, cols) = size.trim().split_once(' ')?;
+                Some((rows.parse().ok()?, cols.parse().ok()?))
+            })
+            .unwrap_or((24, 80))
+    }
+
+    fn draw(&mut self, screen: &str) -> io::Result<()> {
+        self.tty.write_all(screen.as_bytes())?;
+        self.tty.flush()
+    }
+
+    fn next_key(&mut self) -> io::Result<Key> {
+        loop {
+            if let Some(key) = read_key(&mut self.tty)? {
+                return Ok(key);
+            }
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.suspend();
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(File::open("/dev/tty")?).stderr(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed; the TUI needs an interactive terminal"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// 用 $VISUAL 或 $EDITOR 打开文件并跳到对应行（vi、vim、nano、emacs 都支持 +行号）
+fn open_in_editor(hit: &Hit) -> io::Result<()> {
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    if !fs::exists(&hit.path)? {
+        return Err(io::Error::other(format!("{} is inside an archive", hit.path)));
+    }
+    let status = Command::new(program)
+        .args(parts)
+        .arg(format!("+{}", hit.line_number))
+        .arg(&hit.path)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{program} exited with {status}")));
+    }
+    Ok(())
+}
+
+pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    // 管道、重定向或者测试里跑的时候没有人能按键，先报错，不去动 /dev/tty
+    if !io::stdin().is_terminal() {
+        return Err("--tui needs an interactive terminal".into());
+    }
+    let mut browser = Browser::new(config);
+    let mut terminal = Terminal::enter()?;
+    loop {
+        let (rows, cols) = terminal.size();
+        terminal.draw(&browser.render(rows, cols))?;
+        match browser.handle(terminal.next_key()?) {
+            Action::Continue => {}
+            Action::Quit => return Ok(()),
+            Action::Open(i) => {
+                terminal.suspend()?;
+                let opened = open_in_editor(&browser.hits[i]);
+                terminal.resume()?;
+                if let Err(e) = opened {
+                    browser.message = Some(format!("could not open editor: {e}"));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn browser(name: &str, query: &str) -> (Browser, std::path::PathBuf) {
+        let dir = env::temp_dir().join(format!("minigrep-tui-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "one duct\ntwo\nthree duct\n").unwrap();
+        fs::write(dir.join("b.txt"), "ductile\n").unwrap();
+        let args: Vec<String> = ["minigrep", query, dir.to_str().unwrap()].iter().map(|s| s.to_string()).collect();
+        (Browser::new(Config::build(&args).unwrap()), dir)
+    }
+
+    #[test]
+    fn reads_keys_and_escape_sequences() {
+        let mut input: &[u8] = b"j\x1b[A\x1b[6~\r\x7f\xe4\xb8\xad\x1b";
+        let keys: Vec<Key> = std::iter::from_fn(|| read_key(&mut input).unwrap()).collect();
+        assert_eq!(
+            vec![Key::Char('j'), Key::Up, Key::PageDown, Key::Enter, Key::Backspace, Key::Char('中'), Key::Esc],
+            keys
+        );
+    }
+
+    #[test]
+    fn navigates_and_opens_hits() {
+        let (mut browser, dir) = browser("navigate", "duct");
+        assert_eq!(3, browser.hits.len());
+        assert_eq!(Action::Continue, browser.handle(Key::Char('k')));
+        assert_eq!(0, browser.selected);
+        browser.handle(Key::PageDown);
+        assert_eq!(2, browser.selected);
+        assert_eq!(Action::Open(2), browser.handle(Key::Enter));
+        assert_eq!("b.txt", &browser.hits[2].path[browser.hits[2].path.len() - 5..]);
+        assert_eq!(Action::Quit, browser.handle(Key::Char('q')));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refines_query_live() {
+        let (mut browser, dir) = browser("refine", "duct");
+        browser.handle(Key::Char('/'));
+        browser.handle(Key::Char('i'));
+        assert_eq!("ducti", browser.config.query);
+        assert_eq!(1, browser.hits.len());
+        browser.handle(Key::Backspace);
+        assert_eq!(3, browser.hits.len());
+        for c in " AND".chars() {
+            browser.handle(Key::Char(c));
+        }
+        assert_eq!("duct AND", browser.config.query);
+        assert!(browser.message.as_ref().unwrap().starts_with("invalid query"));
+        browser.handle(Key::Esc);
+        assert_eq!(Mode::Browse, browser.mode);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn renders_groups_and_preview() {
+        let (mut browser, dir) = browser("render", "duct");
+        browser.handle(Key::Down);
+        let screen = browser.render(24, 80);
+        assert!(screen.contains("3 matches in 2 files"));
+        assert!(screen.contains(&format!("\x1b[1m{}\x1b[0m", dir.join("a.txt").display())));
+        assert!(screen.contains("\x1b[7m      3: three duct\x1b[0m"));
+        assert!(screen.contains("    2: two"));
+        assert_eq!(24, screen.matches("\r\n").count());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn preview_is_cached_per_hit_and_split_by_terminator() {
+        let (mut browser, dir) = browser("preview", "duct");
+        assert!(browser.render(24, 80).contains("    2: two"));
+        // 选中项没变就不重新读文件
+        fs::write(dir.join("a.txt"), "one duct\nTWO\nthree duct\n").unwrap();
+        assert!(browser.render(24, 80).contains("    2: two"));
+        browser.handle(Key::Down);
+        assert!(browser.render(24, 80).contains("    2: TWO"));
+
+        // --null-data：预览按 NUL 切分，记录里的换行不算新的一行
+        fs::write(dir.join("c.list"), "first\nstill first\0duct\0last\0").unwrap();
+        let path = dir.join("c.list").to_string_lossy().into_owned();
+        let args: Vec<String> = ["minigrep", "--null-data", "duct", &path].iter().map(|s| s.to_string()).collect();
+        let mut browser = Browser::new(Config::build(&args).unwrap());
+        assert_eq!(2, browser.hits[0].line_number);
+        let preview = browser.preview(80);
+        assert_eq!(vec!["    1: first still first", "\x1b[1m    2: duct\x1b[0m", "    3: last"], preview);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn fit_counts_wide_characters() {
+        assert_eq!("我啥", fit("我啥也不是", 5));
+        assert_eq!("a b", fit("a\tbc", 3));
+    }
+}
@@ -1,50 +1,1897 @@
-#[derive(Debug)]  // 编译器：自动生成 Debug trait 实现，用于打印结构体信息
+use std::error::Error;  // 语法：导入标准库的 Error trait，PackageError 要实现它
+use std::fmt;           // 语法：导入 fmt 模块，用于实现 Display
+use std::str::FromStr;  // 语法：实现 FromStr 之后可以用 "1.2 kg".parse::<Weight>()
+use std::time::SystemTime;  // 语法：跟踪事件的时间戳
+
+// 单个包裹的重量上限（30 公斤），更重的货物要走货运
+const MAX_WEIGHT: Weight = Weight::grams(30_000);  // 语法：常量必须写明类型，初始化只能调用 const fn
+                                                  // 编译器：编译期内联到使用处，不占运行时内存
+
+// ISO 3166-1 国家和地区表：二字母代码、三字母代码、数字代码、常用英文名和其他叫法
+// 其他叫法包括 ISO 的正式短名、全称，以及几个常用旧名（Russia、Turkey 等）
+// 内存：整张表在只读数据段里，不在堆上分配
+struct CountryRecord {
+    alpha2: &'static str,
+    alpha3: &'static str,
+    numeric: u16,
+    name: &'static str,
+    other_names: &'static [&'static str],  // 语法：引用切片，长度在编译期确定
+}
+
+const COUNTRIES: &[CountryRecord] = &[
+    CountryRecord { alpha2: "AD", alpha3: "AND", numeric: 20, name: "Andorra", other_names: &["Principality of Andorra"] },
+    CountryRecord { alpha2: "AE", alpha3: "ARE", numeric: 784, name: "United Arab Emirates", other_names: &[] },
+    CountryRecord { alpha2: "AF", alpha3: "AFG", numeric: 4, name: "Afghanistan", other_names: &["Islamic Republic of Afghanistan"] },
+    CountryRecord { alpha2: "AG", alpha3: "ATG", numeric: 28, name: "Antigua and Barbuda", other_names: &[] },
+    CountryRecord { alpha2: "AI", alpha3: "AIA", numeric: 660, name: "Anguilla", other_names: &[] },
+    CountryRecord { alpha2: "AL", alpha3: "ALB", numeric: 8, name: "Albania", other_names: &["Republic of Albania"] },
+    CountryRecord { alpha2: "AM", alpha3: "ARM", numeric: 51, name: "Armenia", other_names: &["Republic of Armenia"] },
+    CountryRecord { alpha2: "AO", alpha3: "AGO", numeric: 24, name: "Angola", other_names: &["Republic of Angola"] },
+    CountryRecord { alpha2: "AQ", alpha3: "ATA", numeric: 10, name: "Antarctica", other_names: &[] },
+    CountryRecord { alpha2: "AR", alpha3: "ARG", numeric: 32, name: "Argentina", other_names: &["Argentine Republic"] },
+    CountryRecord { alpha2: "AS", alpha3: "ASM", numeric: 16, name: "American Samoa", other_names: &[] },
+    CountryRecord { alpha2: "AT", alpha3: "AUT", numeric: 40, name: "Austria", other_names: &["Republic of Austria"] },
+    CountryRecord { alpha2: "AU", alpha3: "AUS", numeric: 36, name: "Australia", other_names: &[] },
+    CountryRecord { alpha2: "AW", alpha3: "ABW", numeric: 533, name: "Aruba", other_names: &[] },
+    CountryRecord { alpha2: "AX", alpha3: "ALA", numeric: 248, name: "Åland Islands", other_names: &[] },
+    CountryRecord { alpha2: "AZ", alpha3: "AZE", numeric: 31, name: "Azerbaijan", other_names: &["Republic of Azerbaijan"] },
+    CountryRecord { alpha2: "BA", alpha3: "BIH", numeric: 70, name: "Bosnia and Herzegovina", other_names: &["Republic of Bosnia and Herzegovina"] },
+    CountryRecord { alpha2: "BB", alpha3: "BRB", numeric: 52, name: "Barbados", other_names: &[] },
+    CountryRecord { alpha2: "BD", alpha3: "BGD", numeric: 50, name: "Bangladesh", other_names: &["People's Republic of Bangladesh"] },
+    CountryRecord { alpha2: "BE", alpha3: "BEL", numeric: 56, name: "Belgium", other_names: &["Kingdom of Belgium"] },
+    CountryRecord { alpha2: "BF", alpha3: "BFA", numeric: 854, name: "Burkina Faso", other_names: &[] },
+    CountryRecord { alpha2: "BG", alpha3: "BGR", numeric: 100, name: "Bulgaria", other_names: &["Republic of Bulgaria"] },
+    CountryRecord { alpha2: "BH", alpha3: "BHR", numeric: 48, name: "Bahrain", other_names: &["Kingdom of Bahrain"] },
+    CountryRecord { alpha2: "BI", alpha3: "BDI", numeric: 108, name: "Burundi", other_names: &["Republic of Burundi"] },
+    CountryRecord { alpha2: "BJ", alpha3: "BEN", numeric: 204, name: "Benin", other_names: &["Republic of Benin"] },
+    CountryRecord { alpha2: "BL", alpha3: "BLM", numeric: 652, name: "Saint Barthélemy", other_names: &[] },
+    CountryRecord { alpha2: "BM", alpha3: "BMU", numeric: 60, name: "Bermuda", other_names: &[] },
+    CountryRecord { alpha2: "BN", alpha3: "BRN", numeric: 96, name: "Brunei Darussalam", other_names: &[] },
+    CountryRecord { alpha2: "BO", alpha3: "BOL", numeric: 68, name: "Bolivia", other_names: &["Bolivia, Plurinational State of", "Plurinational State of Bolivia"] },
+    CountryRecord { alpha2: "BQ", alpha3: "BES", numeric: 535, name: "Bonaire, Sint Eustatius and Saba", other_names: &[] },
+    CountryRecord { alpha2: "BR", alpha3: "BRA", numeric: 76, name: "Brazil", other_names: &["Federative Republic of Brazil"] },
+    CountryRecord { alpha2: "BS", alpha3: "BHS", numeric: 44, name: "Bahamas", other_names: &["Commonwealth of the Bahamas"] },
+    CountryRecord { alpha2: "BT", alpha3: "BTN", numeric: 64, name: "Bhutan", other_names: &["Kingdom of Bhutan"] },
+    CountryRecord { alpha2: "BV", alpha3: "BVT", numeric: 74, name: "Bouvet Island", other_names: &[] },
+    CountryRecord { alpha2: "BW", alpha3: "BWA", numeric: 72, name: "Botswana", other_names: &["Republic of Botswana"] },
+    CountryRecord { alpha2: "BY", alpha3: "BLR", numeric: 112, name: "Belarus", other_names: &["Republic of Belarus"] },
+    CountryRecord { alpha2: "BZ", alpha3: "BLZ", numeric: 84, name: "Belize", other_names: &[] },
+    CountryRecord { alpha2: "CA", alpha3: "CAN", numeric: 124, name: "Canada", other_names: &[] },
+    CountryRecord { alpha2: "CC", alpha3: "CCK", numeric: 166, name: "Cocos (Keeling) Islands", other_names: &[] },
+    CountryRecord { alpha2: "CD", alpha3: "COD", numeric: 180, name: "Congo, The Democratic Republic of the", other_names: &[] },
+    CountryRecord { alpha2: "CF", alpha3: "CAF", numeric: 140, name: "Central African Republic", other_names: &[] },
+    CountryRecord { alpha2: "CG", alpha3: "COG", numeric: 178, name: "Congo", other_names: &["Republic of the Congo"] },
+    CountryRecord { alpha2: "CH", alpha3: "CHE", numeric: 756, name: "Switzerland", other_names: &["Swiss Confederation"] },
+    CountryRecord { alpha2: "CI", alpha3: "CIV", numeric: 384, name: "Côte d'Ivoire", other_names: &["Republic of Côte d'Ivoire"] },
+    CountryRecord { alpha2: "CK", alpha3: "COK", numeric: 184, name: "Cook Islands", other_names: &[] },
+    CountryRecord { alpha2: "CL", alpha3: "CHL", numeric: 152, name: "Chile", other_names: &["Republic of Chile"] },
+    CountryRecord { alpha2: "CM", alpha3: "CMR", numeric: 120, name: "Cameroon", other_names: &["Republic of Cameroon"] },
+    CountryRecord { alpha2: "CN", alpha3: "CHN", numeric: 156, name: "China", other_names: &["People's Republic of China"] },
+    CountryRecord { alpha2: "CO", alpha3: "COL", numeric: 170, name: "Colombia", other_names: &["Republic of Colombia"] },
+    CountryRecord { alpha2: "CR", alpha3: "CRI", numeric: 188, name: "Costa Rica", other_names: &["Republic of Costa Rica"] },
+    CountryRecord { alpha2: "CU", alpha3: "CUB", numeric: 192, name: "Cuba", other_names: &["Republic of Cuba"] },
+    CountryRecord { alpha2: "CV", alpha3: "CPV", numeric: 132, name: "Cabo Verde", other_names: &["Republic of Cabo Verde"] },
+    CountryRecord { alpha2: "CW", alpha3: "CUW", numeric: 531, name: "Curaçao", other_names: &[] },
+    CountryRecord { alpha2: "CX", alpha3: "CXR", numeric: 162, name: "Christmas Island", other_names: &[] },
+    CountryRecord { alpha2: "CY", alpha3: "CYP", numeric: 196, name: "Cyprus", other_names: &["Republic of Cyprus"] },
+    CountryRecord { alpha2: "CZ", alpha3: "CZE", numeric: 203, name: "Czechia", other_names: &["Czech Republic"] },
+    CountryRecord { alpha2: "DE", alpha3: "DEU", numeric: 276, name: "Germany", other_names: &["Federal Republic of Germany"] },
+    CountryRecord { alpha2: "DJ", alpha3: "DJI", numeric: 262, name: "Djibouti", other_names: &["Republic of Djibouti"] },
+    CountryRecord { alpha2: "DK", alpha3: "DNK", numeric: 208, name: "Denmark", other_names: &["Kingdom of Denmark"] },
+    CountryRecord { alpha2: "DM", alpha3: "DMA", numeric: 212, name: "Dominica", other_names: &["Commonwealth of Dominica"] },
+    CountryRecord { alpha2: "DO", alpha3: "DOM", numeric: 214, name: "Dominican Republic", other_names: &[] },
+    CountryRecord { alpha2: "DZ", alpha3: "DZA", numeric: 12, name: "Algeria", other_names: &["People's Democratic Republic of Algeria"] },
+    CountryRecord { alpha2: "EC", alpha3: "ECU", numeric: 218, name: "Ecuador", other_names: &["Republic of Ecuador"] },
+    CountryRecord { alpha2: "EE", alpha3: "EST", numeric: 233, name: "Estonia", other_names: &["Republic of Estonia"] },
+    CountryRecord { alpha2: "EG", alpha3: "EGY", numeric: 818, name: "Egypt", other_names: &["Arab Republic of Egypt"] },
+    CountryRecord { alpha2: "EH", alpha3: "ESH", numeric: 732, name: "Western Sahara", other_names: &[] },
+    CountryRecord { alpha2: "ER", alpha3: "ERI", numeric: 232, name: "Eritrea", other_names: &["the State of Eritrea"] },
+    CountryRecord { alpha2: "ES", alpha3: "ESP", numeric: 724, name: "Spain", other_names: &["Kingdom of Spain"] },
+    CountryRecord { alpha2: "ET", alpha3: "ETH", numeric: 231, name: "Ethiopia", other_names: &["Federal Democratic Republic of Ethiopia"] },
+    CountryRecord { alpha2: "FI", alpha3: "FIN", numeric: 246, name: "Finland", other_names: &["Republic of Finland"] },
+    CountryRecord { alpha2: "FJ", alpha3: "FJI", numeric: 242, name: "Fiji", other_names: &["Republic of Fiji"] },
+    CountryRecord { alpha2: "FK", alpha3: "FLK", numeric: 238, name: "Falkland Islands (Malvinas)", other_names: &[] },
+    CountryRecord { alpha2: "FM", alpha3: "FSM", numeric: 583, name: "Micronesia, Federated States of", other_names: &["Federated States of Micronesia"] },
+    CountryRecord { alpha2: "FO", alpha3: "FRO", numeric: 234, name: "Faroe Islands", other_names: &[] },
+    CountryRecord { alpha2: "FR", alpha3: "FRA", numeric: 250, name: "France", other_names: &["French Republic"] },
+    CountryRecord { alpha2: "GA", alpha3: "GAB", numeric: 266, name: "Gabon", other_names: &["Gabonese Republic"] },
+    CountryRecord { alpha2: "GB", alpha3: "GBR", numeric: 826, name: "United Kingdom", other_names: &["United Kingdom of Great Britain and Northern Ireland"] },
+    CountryRecord { alpha2: "GD", alpha3: "GRD", numeric: 308, name: "Grenada", other_names: &[] },
+    CountryRecord { alpha2: "GE", alpha3: "GEO", numeric: 268, name: "Georgia", other_names: &[] },
+    CountryRecord { alpha2: "GF", alpha3: "GUF", numeric: 254, name: "French Guiana", other_names: &[] },
+    CountryRecord { alpha2: "GG", alpha3: "GGY", numeric: 831, name: "Guernsey", other_names: &[] },
+    CountryRecord { alpha2: "GH", alpha3: "GHA", numeric: 288, name: "Ghana", other_names: &["Republic of Ghana"] },
+    CountryRecord { alpha2: "GI", alpha3: "GIB", numeric: 292, name: "Gibraltar", other_names: &[] },
+    CountryRecord { alpha2: "GL", alpha3: "GRL", numeric: 304, name: "Greenland", other_names: &[] },
+    CountryRecord { alpha2: "GM", alpha3: "GMB", numeric: 270, name: "Gambia", other_names: &["Republic of the Gambia"] },
+    CountryRecord { alpha2: "GN", alpha3: "GIN", numeric: 324, name: "Guinea", other_names: &["Republic of Guinea"] },
+    CountryRecord { alpha2: "GP", alpha3: "GLP", numeric: 312, name: "Guadeloupe", other_names: &[] },
+    CountryRecord { alpha2: "GQ", alpha3: "GNQ", numeric: 226, name: "Equatorial Guinea", other_names: &["Republic of Equatorial Guinea"] },
+    CountryRecord { alpha2: "GR", alpha3: "GRC", numeric: 300, name: "Greece", other_names: &["Hellenic Republic"] },
+    CountryRecord { alpha2: "GS", alpha3: "SGS", numeric: 239, name: "South Georgia and the South Sandwich Islands", other_names: &[] },
+    CountryRecord { alpha2: "GT", alpha3: "GTM", numeric: 320, name: "Guatemala", other_names: &["Republic of Guatemala"] },
+    CountryRecord { alpha2: "GU", alpha3: "GUM", numeric: 316, name: "Guam", other_names: &[] },
+    CountryRecord { alpha2: "GW", alpha3: "GNB", numeric: 624, name: "Guinea-Bissau", other_names: &["Republic of Guinea-Bissau"] },
+    CountryRecord { alpha2: "GY", alpha3: "GUY", numeric: 328, name: "Guyana", other_names: &["Republic of Guyana"] },
+    CountryRecord { alpha2: "HK", alpha3: "HKG", numeric: 344, name: "Hong Kong", other_names: &["Hong Kong Special Administrative Region of China"] },
+    CountryRecord { alpha2: "HM", alpha3: "HMD", numeric: 334, name: "Heard Island and McDonald Islands", other_names: &[] },
+    CountryRecord { alpha2: "HN", alpha3: "HND", numeric: 340, name: "Honduras", other_names: &["Republic of Honduras"] },
+    CountryRecord { alpha2: "HR", alpha3: "HRV", numeric: 191, name: "Croatia", other_names: &["Republic of Croatia"] },
+    CountryRecord { alpha2: "HT", alpha3: "HTI", numeric: 332, name: "Haiti", other_names: &["Republic of Haiti"] },
+    CountryRecord { alpha2: "HU", alpha3: "HUN", numeric: 348, name: "Hungary", other_names: &[] },
+    CountryRecord { alpha2: "ID", alpha3: "IDN", numeric: 360, name: "Indonesia", other_names: &["Republic of Indonesia"] },
+    CountryRecord { alpha2: "IE", alpha3: "IRL", numeric: 372, name: "Ireland", other_names: &[] },
+    CountryRecord { alpha2: "IL", alpha3: "ISR", numeric: 376, name: "Israel", other_names: &["State of Israel"] },
+    CountryRecord { alpha2: "IM", alpha3: "IMN", numeric: 833, name: "Isle of Man", other_names: &[] },
+    CountryRecord { alpha2: "IN", alpha3: "IND", numeric: 356, name: "India", other_names: &["Republic of India"] },
+    CountryRecord { alpha2: "IO", alpha3: "IOT", numeric: 86, name: "British Indian Ocean Territory", other_names: &[] },
+    CountryRecord { alpha2: "IQ", alpha3: "IRQ", numeric: 368, name: "Iraq", other_names: &["Republic of Iraq"] },
+    CountryRecord { alpha2: "IR", alpha3: "IRN", numeric: 364, name: "Iran", other_names: &["Iran, Islamic Republic of", "Islamic Republic of Iran"] },
+    CountryRecord { alpha2: "IS", alpha3: "ISL", numeric: 352, name: "Iceland", other_names: &["Republic of Iceland"] },
+    CountryRecord { alpha2: "IT", alpha3: "ITA", numeric: 380, name: "Italy", other_names: &["Italian Republic"] },
+    CountryRecord { alpha2: "JE", alpha3: "JEY", numeric: 832, name: "Jersey", other_names: &[] },
+    CountryRecord { alpha2: "JM", alpha3: "JAM", numeric: 388, name: "Jamaica", other_names: &[] },
+    CountryRecord { alpha2: "JO", alpha3: "JOR", numeric: 400, name: "Jordan", other_names: &["Hashemite Kingdom of Jordan"] },
+    CountryRecord { alpha2: "JP", alpha3: "JPN", numeric: 392, name: "Japan", other_names: &[] },
+    CountryRecord { alpha2: "KE", alpha3: "KEN", numeric: 404, name: "Kenya", other_names: &["Republic of Kenya"] },
+    CountryRecord { alpha2: "KG", alpha3: "KGZ", numeric: 417, name: "Kyrgyzstan", other_names: &["Kyrgyz Republic"] },
+    CountryRecord { alpha2: "KH", alpha3: "KHM", numeric: 116, name: "Cambodia", other_names: &["Kingdom of Cambodia"] },
+    CountryRecord { alpha2: "KI", alpha3: "KIR", numeric: 296, name: "Kiribati", other_names: &["Republic of Kiribati"] },
+    CountryRecord { alpha2: "KM", alpha3: "COM", numeric: 174, name: "Comoros", other_names: &["Union of the Comoros"] },
+    CountryRecord { alpha2: "KN", alpha3: "KNA", numeric: 659, name: "Saint Kitts and Nevis", other_names: &[] },
+    CountryRecord { alpha2: "KP", alpha3: "PRK", numeric: 408, name: "North Korea", other_names: &["Korea, Democratic People's Republic of", "Democratic People's Republic of Korea"] },
+    CountryRecord { alpha2: "KR", alpha3: "KOR", numeric: 410, name: "South Korea", other_names: &["Korea, Republic of"] },
+    CountryRecord { alpha2: "KW", alpha3: "KWT", numeric: 414, name: "Kuwait", other_names: &["State of Kuwait"] },
+    CountryRecord { alpha2: "KY", alpha3: "CYM", numeric: 136, name: "Cayman Islands", other_names: &[] },
+    CountryRecord { alpha2: "KZ", alpha3: "KAZ", numeric: 398, name: "Kazakhstan", other_names: &["Republic of Kazakhstan"] },
+    CountryRecord { alpha2: "LA", alpha3: "LAO", numeric: 418, name: "Laos", other_names: &["Lao People's Democratic Republic"] },
+    CountryRecord { alpha2: "LB", alpha3: "LBN", numeric: 422, name: "Lebanon", other_names: &["Lebanese Republic"] },
+    CountryRecord { alpha2: "LC", alpha3: "LCA", numeric: 662, name: "Saint Lucia", other_names: &[] },
+    CountryRecord { alpha2: "LI", alpha3: "LIE", numeric: 438, name: "Liechtenstein", other_names: &["Principality of Liechtenstein"] },
+    CountryRecord { alpha2: "LK", alpha3: "LKA", numeric: 144, name: "Sri Lanka", other_names: &["Democratic Socialist Republic of Sri Lanka"] },
+    CountryRecord { alpha2: "LR", alpha3: "LBR", numeric: 430, name: "Liberia", other_names: &["Republic of Liberia"] },
+    CountryRecord { alpha2: "LS", alpha3: "LSO", numeric: 426, name: "Lesotho", other_names: &["Kingdom of Lesotho"] },
+    CountryRecord { alpha2: "LT", alpha3: "LTU", numeric: 440, name: "Lithuania", other_names: &["Republic of Lithuania"] },
+    CountryRecord { alpha2: "LU", alpha3: "LUX", numeric: 442, name: "Luxembourg", other_names: &["Grand Duchy of Luxembourg"] },
+    CountryRecord { alpha2: "LV", alpha3: "LVA", numeric: 428, name: "Latvia", other_names: &["Republic of Latvia"] },
+    CountryRecord { alpha2: "LY", alpha3: "LBY", numeric: 434, name: "Libya", other_names: &[] },
+    CountryRecord { alpha2: "MA", alpha3: "MAR", numeric: 504, name: "Morocco", other_names: &["Kingdom of Morocco"] },
+    CountryRecord { alpha2: "MC", alpha3: "MCO", numeric: 492, name: "Monaco", other_names: &["Principality of Monaco"] },
+    CountryRecord { alpha2: "MD", alpha3: "MDA", numeric: 498, name: "Moldova", other_names: &["Moldova, Republic of", "Republic of Moldova"] },
+    CountryRecord { alpha2: "ME", alpha3: "MNE", numeric: 499, name: "Montenegro", other_names: &[] },
+    CountryRecord { alpha2: "MF", alpha3: "MAF", numeric: 663, name: "Saint Martin (French part)", other_names: &[] },
+    CountryRecord { alpha2: "MG", alpha3: "MDG", numeric: 450, name: "Madagascar", other_names: &["Republic of Madagascar"] },
+    CountryRecord { alpha2: "MH", alpha3: "MHL", numeric: 584, name: "Marshall Islands", other_names: &["Republic of the Marshall Islands"] },
+    CountryRecord { alpha2: "MK", alpha3: "MKD", numeric: 807, name: "North Macedonia", other_names: &["Republic of North Macedonia"] },
+    CountryRecord { alpha2: "ML", alpha3: "MLI", numeric: 466, name: "Mali", other_names: &["Republic of Mali"] },
+    CountryRecord { alpha2: "MM", alpha3: "MMR", numeric: 104, name: "Myanmar", other_names: &["Republic of Myanmar"] },
+    CountryRecord { alpha2: "MN", alpha3: "MNG", numeric: 496, name: "Mongolia", other_names: &[] },
+    CountryRecord { alpha2: "MO", alpha3: "MAC", numeric: 446, name: "Macao", other_names: &["Macao Special Administrative Region of China"] },
+    CountryRecord { alpha2: "MP", alpha3: "MNP", numeric: 580, name: "Northern Mariana Islands", other_names: &["Commonwealth of the Northern Mariana Islands"] },
+    CountryRecord { alpha2: "MQ", alpha3: "MTQ", numeric: 474, name: "Martinique", other_names: &[] },
+    CountryRecord { alpha2: "MR", alpha3: "MRT", numeric: 478, name: "Mauritania", other_names: &["Islamic Republic of Mauritania"] },
+    CountryRecord { alpha2: "MS", alpha3: "MSR", numeric: 500, name: "Montserrat", other_names: &[] },
+    CountryRecord { alpha2: "MT", alpha3: "MLT", numeric: 470, name: "Malta", other_names: &["Republic of Malta"] },
+    CountryRecord { alpha2: "MU", alpha3: "MUS", numeric: 480, name: "Mauritius", other_names: &["Republic of Mauritius"] },
+    CountryRecord { alpha2: "MV", alpha3: "MDV", numeric: 462, name: "Maldives", other_names: &["Republic of Maldives"] },
+    CountryRecord { alpha2: "MW", alpha3: "MWI", numeric: 454, name: "Malawi", other_names: &["Republic of Malawi"] },
+    CountryRecord { alpha2: "MX", alpha3: "MEX", numeric: 484, name: "Mexico", other_names: &["United Mexican States"] },
+    CountryRecord { alpha2: "MY", alpha3: "MYS", numeric: 458, name: "Malaysia", other_names: &[] },
+    CountryRecord { alpha2: "MZ", alpha3: "MOZ", numeric: 508, name: "Mozambique", other_names: &["Republic of Mozambique"] },
+    CountryRecord { alpha2: "NA", alpha3: "NAM", numeric: 516, name: "Namibia", other_names: &["Republic of Namibia"] },
+    CountryRecord { alpha2: "NC", alpha3: "NCL", numeric: 540, name: "New Caledonia", other_names: &[] },
+    CountryRecord { alpha2: "NE", alpha3: "NER", numeric: 562, name: "Niger", other_names: &["Republic of the Niger"] },
+    CountryRecord { alpha2: "NF", alpha3: "NFK", numeric: 574, name: "Norfolk Island", other_names: &[] },
+    CountryRecord { alpha2: "NG", alpha3: "NGA", numeric: 566, name: "Nigeria", other_names: &["Federal Republic of Nigeria"] },
+    CountryRecord { alpha2: "NI", alpha3: "NIC", numeric: 558, name: "Nicaragua", other_names: &["Republic of Nicaragua"] },
+    CountryRecord { alpha2: "NL", alpha3: "NLD", numeric: 528, name: "Netherlands", other_names: &["Kingdom of the Netherlands"] },
+    CountryRecord { alpha2: "NO", alpha3: "NOR", numeric: 578, name: "Norway", other_names: &["Kingdom of Norway"] },
+    CountryRecord { alpha2: "NP", alpha3: "NPL", numeric: 524, name: "Nepal", other_names: &["Federal Democratic Republic of Nepal"] },
+    CountryRecord { alpha2: "NR", alpha3: "NRU", numeric: 520, name: "Nauru", other_names: &["Republic of Nauru"] },
+    CountryRecord { alpha2: "NU", alpha3: "NIU", numeric: 570, name: "Niue", other_names: &[] },
+    CountryRecord { alpha2: "NZ", alpha3: "NZL", numeric: 554, name: "New Zealand", other_names: &[] },
+    CountryRecord { alpha2: "OM", alpha3: "OMN", numeric: 512, name: "Oman", other_names: &["Sultanate of Oman"] },
+    CountryRecord { alpha2: "PA", alpha3: "PAN", numeric: 591, name: "Panama", other_names: &["Republic of Panama"] },
+    CountryRecord { alpha2: "PE", alpha3: "PER", numeric: 604, name: "Peru", other_names: &["Republic of Peru"] },
+    CountryRecord { alpha2: "PF", alpha3: "PYF", numeric: 258, name: "French Polynesia", other_names: &[] },
+    CountryRecord { alpha2: "PG", alpha3: "PNG", numeric: 598, name: "Papua New Guinea", other_names: &["Independent State of Papua New Guinea"] },
+    CountryRecord { alpha2: "PH", alpha3: "PHL", numeric: 608, name: "Philippines", other_names: &["Republic of the Philippines"] },
+    CountryRecord { alpha2: "PK", alpha3: "PAK", numeric: 586, name: "Pakistan", other_names: &["Islamic Republic of Pakistan"] },
+    CountryRecord { alpha2: "PL", alpha3: "POL", numeric: 616, name: "Poland", other_names: &["Republic of Poland"] },
+    CountryRecord { alpha2: "PM", alpha3: "SPM", numeric: 666, name: "Saint Pierre and Miquelon", other_names: &[] },
+    CountryRecord { alpha2: "PN", alpha3: "PCN", numeric: 612, name: "Pitcairn", other_names: &[] },
+    CountryRecord { alpha2: "PR", alpha3: "PRI", numeric: 630, name: "Puerto Rico", other_names: &[] },
+    CountryRecord { alpha2: "PS", alpha3: "PSE", numeric: 275, name: "Palestine, State of", other_names: &["the State of Palestine"] },
+    CountryRecord { alpha2: "PT", alpha3: "PRT", numeric: 620, name: "Portugal", other_names: &["Portuguese Republic"] },
+    CountryRecord { alpha2: "PW", alpha3: "PLW", numeric: 585, name: "Palau", other_names: &["Republic of Palau"] },
+    CountryRecord { alpha2: "PY", alpha3: "PRY", numeric: 600, name: "Paraguay", other_names: &["Republic of Paraguay"] },
+    CountryRecord { alpha2: "QA", alpha3: "QAT", numeric: 634, name: "Qatar", other_names: &["State of Qatar"] },
+    CountryRecord { alpha2: "RE", alpha3: "REU", numeric: 638, name: "Réunion", other_names: &[] },
+    CountryRecord { alpha2: "RO", alpha3: "ROU", numeric: 642, name: "Romania", other_names: &[] },
+    CountryRecord { alpha2: "RS", alpha3: "SRB", numeric: 688, name: "Serbia", other_names: &["Republic of Serbia"] },
+    CountryRecord { alpha2: "RU", alpha3: "RUS", numeric: 643, name: "Russian Federation", other_names: &["Russia"] },
+    CountryRecord { alpha2: "RW", alpha3: "RWA", numeric: 646, name: "Rwanda", other_names: &["Rwandese Republic"] },
+    CountryRecord { alpha2: "SA", alpha3: "SAU", numeric: 682, name: "Saudi Arabia", other_names: &["Kingdom of Saudi Arabia"] },
+    CountryRecord { alpha2: "SB", alpha3: "SLB", numeric: 90, name: "Solomon Islands", other_names: &[] },
+    CountryRecord { alpha2: "SC", alpha3: "SYC", numeric: 690, name: "Seychelles", other_names: &["Republic of Seychelles"] },
+    CountryRecord { alpha2: "SD", alpha3: "SDN", numeric: 729, name: "Sudan", other_names: &["Republic of the Sudan"] },
+    CountryRecord { alpha2: "SE", alpha3: "SWE", numeric: 752, name: "Sweden", other_names: &["Kingdom of Sweden"] },
+    CountryRecord { alpha2: "SG", alpha3: "SGP", numeric: 702, name: "Singapore", other_names: &["Republic of Singapore"] },
+    CountryRecord { alpha2: "SH", alpha3: "SHN", numeric: 654, name: "Saint Helena, Ascension and Tristan da Cunha", other_names: &[] },
+    CountryRecord { alpha2: "SI", alpha3: "SVN", numeric: 705, name: "Slovenia", other_names: &["Republic of Slovenia"] },
+    CountryRecord { alpha2: "SJ", alpha3: "SJM", numeric: 744, name: "Svalbard and Jan Mayen", other_names: &[] },
+    CountryRecord { alpha2: "SK", alpha3: "SVK", numeric: 703, name: "Slovakia", other_names: &["Slovak Republic"] },
+    CountryRecord { alpha2: "SL", alpha3: "SLE", numeric: 694, name: "Sierra Leone", other_names: &["Republic of Sierra Leone"] },
+    CountryRecord { alpha2: "SM", alpha3: "SMR", numeric: 674, name: "San Marino", other_names: &["Republic of San Marino"] },
+    CountryRecord { alpha2: "SN", alpha3: "SEN", numeric: 686, name: "Senegal", other_names: &["Republic of Senegal"] },
+    CountryRecord { alpha2: "SO", alpha3: "SOM", numeric: 706, name: "Somalia", other_names: &["Federal Republic of Somalia"] },
+    CountryRecord { alpha2: "SR", alpha3: "SUR", numeric: 740, name: "Suriname", other_names: &["Republic of Suriname"] },
+    CountryRecord { alpha2: "SS", alpha3: "SSD", numeric: 728, name: "South Sudan", other_names: &["Republic of South Sudan"] },
+    CountryRecord { alpha2: "ST", alpha3: "STP", numeric: 678, name: "Sao Tome and Principe", other_names: &["Democratic Republic of Sao Tome and Principe"] },
+    CountryRecord { alpha2: "SV", alpha3: "SLV", numeric: 222, name: "El Salvador", other_names: &["Republic of El Salvador"] },
+    CountryRecord { alpha2: "SX", alpha3: "SXM", numeric: 534, name: "Sint Maarten (Dutch part)", other_names: &[] },
+    CountryRecord { alpha2: "SY", alpha3: "SYR", numeric: 760, name: "Syria", other_names: &["Syrian Arab Republic"] },
+    CountryRecord { alpha2: "SZ", alpha3: "SWZ", numeric: 748, name: "Eswatini", other_names: &["Kingdom of Eswatini"] },
+    CountryRecord { alpha2: "TC", alpha3: "TCA", numeric: 796, name: "Turks and Caicos Islands", other_names: &[] },
+    CountryRecord { alpha2: "TD", alpha3: "TCD", numeric: 148, name: "Chad", other_names: &["Republic of Chad"] },
+    CountryRecord { alpha2: "TF", alpha3: "ATF", numeric: 260, name: "French Southern Territories", other_names: &[] },
+    CountryRecord { alpha2: "TG", alpha3: "TGO", numeric: 768, name: "Togo", other_names: &["Togolese Republic"] },
+    CountryRecord { alpha2: "TH", alpha3: "THA", numeric: 764, name: "Thailand", other_names: &["Kingdom of Thailand"] },
+    CountryRecord { alpha2: "TJ", alpha3: "TJK", numeric: 762, name: "Tajikistan", other_names: &["Republic of Tajikistan"] },
+    CountryRecord { alpha2: "TK", alpha3: "TKL", numeric: 772, name: "Tokelau", other_names: &[] },
+    CountryRecord { alpha2: "TL", alpha3: "TLS", numeric: 626, name: "Timor-Leste", other_names: &["Democratic Republic of Timor-Leste"] },
+    CountryRecord { alpha2: "TM", alpha3: "TKM", numeric: 795, name: "Turkmenistan", other_names: &[] },
+    CountryRecord { alpha2: "TN", alpha3: "TUN", numeric: 788, name: "Tunisia", other_names: &["Republic of Tunisia"] },
+    CountryRecord { alpha2: "TO", alpha3: "TON", numeric: 776, name: "Tonga", other_names: &["Kingdom of Tonga"] },
+    CountryRecord { alpha2: "TR", alpha3: "TUR", numeric: 792, name: "Türkiye", other_names: &["Republic of Türkiye", "Turkey"] },
+    CountryRecord { alpha2: "TT", alpha3: "TTO", numeric: 780, name: "Trinidad and Tobago", other_names: &["Republic of Trinidad and Tobago"] },
+    CountryRecord { alpha2: "TV", alpha3: "TUV", numeric: 798, name: "Tuvalu", other_names: &[] },
+    CountryRecord { alpha2: "TW", alpha3: "TWN", numeric: 158, name: "Taiwan", other_names: &["Taiwan, Province of China"] },
+    CountryRecord { alpha2: "TZ", alpha3: "TZA", numeric: 834, name: "Tanzania", other_names: &["Tanzania, United Republic of", "United Republic of Tanzania"] },
+    CountryRecord { alpha2: "UA", alpha3: "UKR", numeric: 804, name: "Ukraine", other_names: &[] },
+    CountryRecord { alpha2: "UG", alpha3: "UGA", numeric: 800, name: "Uganda", other_names: &["Republic of Uganda"] },
+    CountryRecord { alpha2: "UM", alpha3: "UMI", numeric: 581, name: "United States Minor Outlying Islands", other_names: &[] },
+    CountryRecord { alpha2: "US", alpha3: "USA", numeric: 840, name: "United States", other_names: &["United States of America"] },
+    CountryRecord { alpha2: "UY", alpha3: "URY", numeric: 858, name: "Uruguay", other_names: &["Eastern Republic of Uruguay"] },
+    CountryRecord { alpha2: "UZ", alpha3: "UZB", numeric: 860, name: "Uzbekistan", other_names: &["Republic of Uzbekistan"] },
+    CountryRecord { alpha2: "VA", alpha3: "VAT", numeric: 336, name: "Holy See (Vatican City State)", other_names: &[] },
+    CountryRecord { alpha2: "VC", alpha3: "VCT", numeric: 670, name: "Saint Vincent and the Grenadines", other_names: &[] },
+    CountryRecord { alpha2: "VE", alpha3: "VEN", numeric: 862, name: "Venezuela", other_names: &["Venezuela, Bolivarian Republic of", "Bolivarian Republic of Venezuela"] },
+    CountryRecord { alpha2: "VG", alpha3: "VGB", numeric: 92, name: "Virgin Islands, British", other_names: &["British Virgin Islands"] },
+    CountryRecord { alpha2: "VI", alpha3: "VIR", numeric: 850, name: "Virgin Islands, U.S.", other_names: &["Virgin Islands of the United States"] },
+    CountryRecord { alpha2: "VN", alpha3: "VNM", numeric: 704, name: "Vietnam", other_names: &["Viet Nam", "Socialist Republic of Viet Nam"] },
+    CountryRecord { alpha2: "VU", alpha3: "VUT", numeric: 548, name: "Vanuatu", other_names: &["Republic of Vanuatu"] },
+    CountryRecord { alpha2: "WF", alpha3: "WLF", numeric: 876, name: "Wallis and Futuna", other_names: &[] },
+    CountryRecord { alpha2: "WS", alpha3: "WSM", numeric: 882, name: "Samoa", other_names: &["Independent State of Samoa"] },
+    CountryRecord { alpha2: "YE", alpha3: "YEM", numeric: 887, name: "Yemen", other_names: &["Republic of Yemen"] },
+    CountryRecord { alpha2: "YT", alpha3: "MYT", numeric: 175, name: "Mayotte", other_names: &[] },
+    CountryRecord { alpha2: "ZA", alpha3: "ZAF", numeric: 710, name: "South Africa", other_names: &["Republic of South Africa"] },
+    CountryRecord { alpha2: "ZM", alpha3: "ZMB", numeric: 894, name: "Zambia", other_names: &["Republic of Zambia"] },
+    CountryRecord { alpha2: "ZW", alpha3: "ZWE", numeric: 716, name: "Zimbabwe", other_names: &["Republic of Zimbabwe"] },
+];
+
+// 国家，内部只存 ISO 3166-1 二字母代码，"Spain"、"spain"、"ES"、"ESP" 解析出来都是同一个值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]  // 编译器：Copy 之后比较、传参都是按位复制 2 个字节
+struct Country([u8; 2]);
+
+impl Country {
+    // 按二字母代码、三字母代码或任一名称查找，不区分大小写，忽略首尾空白
+    fn parse(text: &str) -> Option<Country> {
+        let text = text.trim();
+        COUNTRIES
+            .iter()
+            .find(|record| {
+                record.alpha2.eq_ignore_ascii_case(text)
+                    || record.alpha3.eq_ignore_ascii_case(text)
+                    || record.name.eq_ignore_ascii_case(text)
+                    || record.other_names.iter().any(|name| name.eq_ignore_ascii_case(text))
+            })
+            .map(|record| {
+                let bytes = record.alpha2.as_bytes();
+                Country([bytes[0], bytes[1]])
+            })
+    }
+
+    fn record(&self) -> &'static CountryRecord {
+        // 编译器：Country 只能由 parse 创建，表里一定有对应的记录
+        COUNTRIES.iter().find(|record| record.alpha2.as_bytes() == self.0).expect("country comes from the table")
+    }
+
+    fn alpha2(&self) -> &'static str {
+        self.record().alpha2
+    }
+
+    fn alpha3(&self) -> &'static str {
+        self.record().alpha3
+    }
+
+    fn numeric(&self) -> u16 {
+        self.record().numeric
+    }
+
+    fn name(&self) -> &'static str {
+        self.record().name
+    }
+}
+
+// 显示常用英文名，比如 "South Korea" 而不是 "Korea, Republic of"
+impl fmt::Display for Country {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// 重量单位
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WeightUnit {
+    Gram,
+    Kilogram,
+    Pound,
+    Ounce,
+}
+
+const NANOGRAMS_PER_GRAM: i64 = 1_000_000_000;
+
+impl WeightUnit {
+    // 1 个单位是多少纳克
+    // 1 磅按定义是 453.59237 克，1 盎司是 1/16 磅 = 28.349523125 克，换成纳克都是整数
+    fn nanograms(self) -> i64 {
+        match self {
+            WeightUnit::Gram => NANOGRAMS_PER_GRAM,
+            WeightUnit::Kilogram => 1_000 * NANOGRAMS_PER_GRAM,
+            WeightUnit::Pound => 453_592_370_000,
+            WeightUnit::Ounce => 28_349_523_125,
+        }
+    }
+
+    // 不区分大小写，接受缩写和英文全称
+    fn parse(text: &str) -> Option<WeightUnit> {
+        match text.to_ascii_lowercase().as_str() {
+            "g" | "gram" | "grams" => Some(WeightUnit::Gram),
+            "kg" | "kilo" | "kilos" | "kilogram" | "kilograms" => Some(WeightUnit::Kilogram),
+            "lb" | "lbs" | "pound" | "pounds" => Some(WeightUnit::Pound),
+            "oz" | "ounce" | "ounces" => Some(WeightUnit::Ounce),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum WeightError {
+    // 数字部分不是非负的十进制数，保存整个输入
+    InvalidNumber(String),
+    // 只有数字没有单位
+    MissingUnit,
+    UnknownUnit(String),
+    // 换算成纳克后超出 i64
+    Overflow,
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WeightError::InvalidNumber(text) => write!(f, "invalid weight {text:?}"),
+            WeightError::MissingUnit => write!(f, "weight needs a unit (g, kg, lb or oz)"),
+            WeightError::UnknownUnit(unit) => write!(f, "unknown weight unit {unit:?}"),
+            WeightError::Overflow => write!(f, "weight out of range"),
+        }
+    }
+}
+
+impl Error for WeightError {}
+
+// 重量，以纳克为单位的整数
+// 克、千克、磅、盎司的整数值换算进来都没有误差，不会把千克当成克传进来
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]  // 编译器：Ord 让重量可以直接比较大小、取 max
+struct Weight(i64);  // 内存：8 字节，上限约 9200 吨
+
+impl Weight {
+    // 语法：const fn 可以在常量里调用；i32 的克数换成纳克不会溢出 i64
+    const fn grams(grams: i32) -> Weight {
+        Weight(grams as i64 * NANOGRAMS_PER_GRAM)
+    }
+
+    fn new(amount: i64, unit: WeightUnit) -> Result<Weight, WeightError> {
+        amount.checked_mul(unit.nanograms()).map(Weight).ok_or(WeightError::Overflow)
+    }
+
+    // 换算成指定单位，只有正好是整数个单位时才返回，不做任何舍入
+    fn in_unit(self, unit: WeightUnit) -> Option<i64> {
+        if self.0 % unit.nanograms() == 0 { Some(self.0 / unit.nanograms()) } else { None }
+    }
+
+    // 计费按克，不足 1 克按 1 克算
+    fn ceil_grams(self) -> i64 {
+        self.0.div_euclid(NANOGRAMS_PER_GRAM) + i64::from(self.0.rem_euclid(NANOGRAMS_PER_GRAM) != 0)
+    }
+}
+
+// "1.2 kg"、"3lb"、"16 Ounces"：数字和单位之间的空格可有可无
+// 小数部分换算成纳克后四舍五入，比如 "0.1 oz" 是 2834952312.5 ng -> 2834952313 ng
+impl FromStr for Weight {
+    type Err = WeightError;
+
+    fn from_str(text: &str) -> Result<Weight, WeightError> {
+        let text = text.trim();
+        let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+        let (number, unit) = (&text[..split], text[split..].trim());
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if (whole.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+            return Err(WeightError::InvalidNumber(text.to_string()));
+        }
+        if unit.is_empty() {
+            return Err(WeightError::MissingUnit);
+        }
+        let unit = WeightUnit::parse(unit).ok_or_else(|| WeightError::UnknownUnit(unit.to_string()))?;
+
+        // 编译器：whole 只含数字，parse 失败只可能是太大
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| WeightError::Overflow)? };
+        let mut weight = Weight::new(whole, unit)?;
+        if !fraction.is_empty() {
+            // 超过 18 位的小数已经小于 1 纳克，截掉
+            let digits = &fraction[..fraction.len().min(18)];
+            let denominator = 10i128.pow(digits.len() as u32);
+            // 内存：用 i128 避免 18 位小数乘以单位纳克数时溢出
+            let numerator = digits.parse::<i128>().expect("fraction is all digits") * unit.nanograms() as i128;
+            let part = (numerator * 2 + denominator) / (denominator * 2);
+            // 编译器：part 不超过一个单位的纳克数，一定能放进 i64
+            weight.0 = weight.0.checked_add(part as i64).ok_or(WeightError::Overflow)?;
+        }
+        Ok(weight)
+    }
+}
+
+// 按克显示，去掉小数末尾的 0：比如 "1200 g"、"453.59237 g"
+impl fmt::Display for Weight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let (grams, fraction) = (abs / NANOGRAMS_PER_GRAM as u64, abs % NANOGRAMS_PER_GRAM as u64);
+        if fraction == 0 {
+            return write!(f, "{sign}{grams} g");
+        }
+        let fraction = format!("{fraction:09}");
+        write!(f, "{sign}{grams}.{} g", fraction.trim_end_matches('0'))
+    }
+}
+
+// 长度单位
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LengthUnit {
+    Micrometre,
+    Millimetre,
+    Centimetre,
+    Inch,
+}
+
+impl LengthUnit {
+    // 1 个单位是多少微米：整数换算，英寸（25.4 mm）也不会有舍入误差
+    fn micrometres(self) -> i64 {
+        match self {
+            LengthUnit::Micrometre => 1,
+            LengthUnit::Millimetre => 1_000,
+            LengthUnit::Centimetre => 10_000,
+            LengthUnit::Inch => 25_400,
+        }
+    }
+}
+
+// 包裹尺寸，统一按微米存储
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dimensions {
+    length_um: i64,
+    width_um: i64,
+    height_um: i64,
+}
+
+// 常用的体积重系数：每公斤对应 5000 立方厘米
+const DEFAULT_VOLUMETRIC_DIVISOR: i64 = 5_000;
+
+impl Dimensions {
+    fn new(length: i64, width: i64, height: i64, unit: LengthUnit) -> Result<Dimensions, PackageError> {
+        for side in [length, width, height] {
+            if side <= 0 {
+                return Err(PackageError::NonPositiveDimension(side));
+            }
+        }
+        let scale = |side: i64| side.checked_mul(unit.micrometres()).ok_or(PackageError::OversizedDimension(side));
+        Ok(Dimensions { length_um: scale(length)?, width_um: scale(width)?, height_um: scale(height)? })
+    }
+
+    // 体积重（克）= 体积（立方厘米）/ divisor * 1000
+    // divisor 是承运商规定的每公斤立方厘米数，比如 5000
+    // 1 立方厘米 = 10^12 立方微米，约掉之后纳克数正好是立方微米数 / divisor，不足 1 纳克向上取整
+    // 内存：体积可能超过 i64，中间结果用 i128；超出 i64 的结果截到最大值
+    // divisor 来自运价表，不是正数时没法算，返回 None，不 panic
+    fn volumetric_weight(&self, divisor: i64) -> Option<Weight> {
+        if divisor <= 0 {
+            return None;
+        }
+        let cubic_um = self.length_um as i128 * self.width_um as i128 * self.height_um as i128;
+        let nanograms = (cubic_um + divisor as i128 - 1) / divisor as i128;
+        Some(Weight(i64::try_from(nanograms).unwrap_or(i64::MAX)))
+    }
+}
+
+#[derive(Debug, PartialEq)]  // 编译器：自动生成 Debug trait 实现，用于打印结构体信息
 struct Package {    // 语法：定义结构体，内存中按字段顺序布局
-    sender_country: String,  // 语法：String 类型（动态字符串）
-                          // 编译器：检查字符串操作安全性
-                          // 内存：栈上存储胖指针（24字节），指向堆上字符串数据
-    recipient_country: String,  // 同上
-    weight_in_grams: i32,       // 语法：32位有符号整数
+    sender_country: Country,  // 语法：元组结构体类型，只能通过 Country::parse 得到
+                          // 编译器：不能再把任意字符串放进来
+                          // 内存：栈上只占 2 字节，不再指向堆上的字符串数据
+    recipient_country: Country,  // 同上
+    weight: Weight,       // 语法：newtype，不能直接传一个裸整数
                           // 编译器：类型检查，内存对齐
-                          // 内存：栈上占4字节
+                          // 内存：栈上占8字节
+    dimensions: Option<Dimensions>,  // 语法：没有量过尺寸的包裹是 None，只按实重计费
+}
+
+// 国家字段属于寄件方还是收件方，错误信息里用来指明是哪个字段
+#[derive(Debug, Clone, Copy, PartialEq)]  // 编译器：Copy 让枚举按位复制，不涉及所有权转移
+enum Party {
+    Sender,
+    Recipient,
+}
+
+impl fmt::Display for Party {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {  // 语法：match 必须覆盖所有变体，编译器会检查
+            Party::Sender => write!(f, "sender"),
+            Party::Recipient => write!(f, "recipient"),
+        }
+    }
+}
+
+// 创建包裹失败的原因，调用方可以按变体分别处理，而不是整个程序 panic
+#[derive(Debug, Clone, PartialEq)]
+enum PackageError {
+    // 重量为 0 或负数
+    NonPositiveWeight(Weight),
+    // 超过 MAX_WEIGHT
+    Overweight(Weight),
+    // 长、宽或高为 0 或负数
+    NonPositiveDimension(i64),
+    // 尺寸大到换算成微米时溢出
+    OversizedDimension(i64),
+    // 国家为空或全是空白
+    EmptyCountry(Party),
+    // 在 ISO 3166 表里找不到的国家名或代码
+    UnknownCountry(Party, String),  // 内存：String 在堆上保存原始输入，方便报错
+}
+
+impl fmt::Display for PackageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackageError::NonPositiveWeight(weight) => {
+                write!(f, "can not ship a weightless package ({weight})")
+            }
+            PackageError::Overweight(weight) => {
+                write!(f, "package weighs {weight}, the limit is {MAX_WEIGHT}")
+            }
+            PackageError::NonPositiveDimension(side) => write!(f, "package side must be positive, got {side}"),
+            PackageError::OversizedDimension(side) => write!(f, "package side {side} is too large"),
+            PackageError::EmptyCountry(party) => write!(f, "{party} country is empty"),
+            PackageError::UnknownCountry(party, name) => write!(f, "unknown {party} country {name:?}"),
+        }
+    }
+}
+
+impl Error for PackageError {}  // 语法：Error 的方法都有默认实现，空 impl 即可
+
+// 解析寄件方或收件方的国家，失败时带上是哪一方
+fn parse_country(name: &str, party: Party) -> Result<Country, PackageError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(PackageError::EmptyCountry(party));
+    }
+    Country::parse(name).ok_or_else(|| PackageError::UnknownCountry(party, name.to_string()))  // 语法：Option 转成 Result
+}
+
+// 贸易区：一组国家，比如欧盟、欧洲自由贸易联盟
+// 关税同盟（欧盟）成员之间寄包裹不用报关；自由贸易区（EFTA）只是免关税，仍然要报关
+#[derive(Debug, Clone, PartialEq)]
+struct TradeZone {
+    name: String,
+    members: Vec<Country>,  // 内存：Vec 在堆上，每个成员 2 字节
+    customs_union: bool,
+}
+
+impl TradeZone {
+    fn new(name: &str, members: Vec<Country>, customs_union: bool) -> TradeZone {
+        TradeZone { name: name.to_string(), members, customs_union }
+    }
+
+    // 成员用 ISO 代码写死，表里一定能找到
+    fn from_codes(name: &str, codes: &str, customs_union: bool) -> TradeZone {
+        let members = codes.split(' ').map(|code| Country::parse(code).expect("trade zone member is in the ISO table")).collect();
+        TradeZone::new(name, members, customs_union)
+    }
+
+    fn contains(&self, country: Country) -> bool {
+        self.members.contains(&country)
+    }
+}
+
+// 可配置的贸易区列表，调用方可以增删成员或加入新的贸易协定
+#[derive(Debug, Clone, PartialEq)]
+struct TradeZones {
+    zones: Vec<TradeZone>,
+}
+
+impl TradeZones {
+    // 欧盟 27 国（关税同盟）和 EFTA 4 国（自由贸易区）
+    fn europe() -> TradeZones {
+        TradeZones {
+            zones: vec![
+                TradeZone::from_codes(
+                    "EU",
+                    "AT BE BG CY CZ DE DK EE ES FI FR GR HR HU IE IT LT LU LV MT NL PL PT RO SE SI SK",
+                    true,
+                ),
+                TradeZone::from_codes("EFTA", "CH IS LI NO", false),
+            ],
+        }
+    }
+
+    // 两个国家都在其中的贸易区
+    fn shared(&self, a: Country, b: Country) -> impl Iterator<Item = &TradeZone> {  // 语法：返回的迭代器借用 self
+        self.zones.iter().filter(move |zone| zone.contains(a) && zone.contains(b))
+    }
+}
+
+// 包裹路线的分类
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Route {
+    // 同一个国家内
+    Domestic,
+    // 跨国，但两国在同一个贸易区
+    IntraZone,
+    // 跨国且没有共同的贸易区
+    ExtraZone,
+}
+
+impl Package {  // 语法：为 Package 结构体实现方法
+    // 构造函数，创建 Package 实例；参数不合法时 panic
+    // 接收外部数据（订单、表格）时用 try_new，一条坏数据不会让整个服务崩溃
+    fn new(sender_country: String, recipient_country: String, weight: Weight) -> Package {
+        // 语法：unwrap_or_else 在 Err 时调用闭包，panic! 宏终止程序
+        Package::try_new(sender_country, recipient_country, weight).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    // 校验所有字段，返回 Result 而不是 panic
+    // 国家可以写名称或 ISO 代码，"spain"、"Spain" 和 "ES" 被视为同一个国家
+    fn try_new(sender_country: String, recipient_country: String, weight: Weight) -> Result<Package, PackageError> {
+        // 编译器：? 运算符在 Err 时提前返回，类型必须都是 PackageError
+        if weight <= Weight::grams(0) {
+            return Err(PackageError::NonPositiveWeight(weight));
+        }
+        if weight > MAX_WEIGHT {
+            return Err(PackageError::Overweight(weight));
+        }
+        // 内存：传入的 String 只用来解析，函数结束时释放
+        Ok(Package {
+            sender_country: parse_country(&sender_country, Party::Sender)?,
+            recipient_country: parse_country(&recipient_country, Party::Recipient)?,
+            weight,
+            dimensions: None,
+        })
+    }
+
+    // 记录量好的尺寸
+    // 语法：按值接收 self 再返回，可以链式调用 Package::new(...).with_dimensions(...)
+    fn with_dimensions(mut self, dimensions: Dimensions) -> Package {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    // 计费重量：实重和体积重取大的
+    // divisor 不是正数时返回 None，没有尺寸的包裹也一样，坏的运价表不会因为碰巧没量尺寸就被放过
+    fn chargeable_weight(&self, divisor: i64) -> Option<Weight> {
+        if divisor <= 0 {
+            return None;
+        }
+        match self.dimensions {
+            Some(dimensions) => Some(self.weight.max(dimensions.volumetric_weight(divisor)?)),
+            None => Some(self.weight),
+        }
+    }
+
+    // 判断是否为国际包裹
+    fn is_international(&self) -> bool {  // 语法：借用 self 的不可变引用
+                                        // 编译器：确保引用有效性，检查字符串比较
+                                        // 内存：通过引用访问字段，不移动数据
+        self.sender_country != self.recipient_country  // 语法：比较两个 Country 的代码
+                                                    // 编译器：PartialEq 由 derive 生成
+                                                    // 内存：只比较栈上的 2 个字节
+    }
+
+    // 包裹属于哪个运费区域
+    fn zone(&self) -> Zone {
+        if self.is_international() { Zone::International } else { Zone::Domestic }
+    }
+
+    // 按贸易区分类：国内、区内、区外
+    fn route(&self, zones: &TradeZones) -> Route {
+        if !self.is_international() {
+            Route::Domestic
+        } else if zones.shared(self.sender_country, self.recipient_country).next().is_some() {
+            Route::IntraZone
+        } else {
+            Route::ExtraZone
+        }
+    }
+
+    // 是否需要海关申报：国内件和同一关税同盟内的包裹不需要，其他跨境包裹都需要
+    fn requires_customs(&self, zones: &TradeZones) -> bool {
+        self.is_international() && !zones.shared(self.sender_country, self.recipient_country).any(|zone| zone.customs_union)
+    }
+
+    // 按运价表计算运输费用，金额带币种
+    fn get_fees(&self, tariff: &Tariff) -> Result<Money, TariffError> {  // 语法：借用运价表，不获取所有权
+        let weight = self
+            .chargeable_weight(tariff.volumetric_divisor)
+            .ok_or(TariffError::InvalidVolumetricDivisor(tariff.volumetric_divisor))?;
+        tariff.fee(weight, self.zone())
+    }
+}
+
+// 一票货：同一次寄出的多个包裹，按包裹分别算计费重量，再汇总
+#[derive(Debug, Default)]  // 语法：Default 生成一个空的 Shipment
+struct Shipment {
+    packages: Vec<Package>,  // 内存：Shipment 拥有这些包裹，drop 时一起释放
+}
+
+impl Shipment {
+    fn new() -> Shipment {
+        Shipment::default()
+    }
+
+    fn add(&mut self, package: Package) {  // 语法：package 的所有权转移进 Shipment
+        self.packages.push(package);
+    }
+
+    // 每个包裹的计费重量，顺序和加入的顺序一致
+    // 语法：Option 也能 collect，有一个是 None 结果就是 None
+    fn chargeable_weights(&self, divisor: i64) -> Option<Vec<Weight>> {
+        self.packages.iter().map(|package| package.chargeable_weight(divisor)).collect()
+    }
+
+    fn total_weight(&self) -> Weight {
+        total(self.packages.iter().map(|package| package.weight))
+    }
+
+    // 逐个包裹取大后再求和，不是总实重和总体积重取大
+    fn total_chargeable_weight(&self, divisor: i64) -> Option<Weight> {
+        Some(total(self.chargeable_weights(divisor)?.into_iter()))
+    }
+}
+
+// 重量求和；超过 i64 纳克（约 9200 吨）时截到最大值，不回绕
+fn total(weights: impl Iterator<Item = Weight>) -> Weight {
+    Weight(weights.fold(0i64, |sum, weight| sum.saturating_add(weight.0)))
+}
+
+// 运单清单（manifest）的导入导出
+// 没有用 serde：这个文件是单独编译的，没有 Cargo 依赖，CSV 和 JSON 都手写，只处理清单用到的部分
+// 每条记录的字段：sender、recipient（国家名或 ISO 代码）、weight（带单位，比如 "1.2 kg"）、
+// 可选的 length_mm、width_mm、height_mm（三个要么都有要么都没有，最多 3 位小数）
+
+const CSV_HEADER: &str = "sender,recipient,weight,length_mm,width_mm,height_mm";
+const DIMENSION_FIELDS: [&str; 3] = ["length_mm", "width_mm", "height_mm"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ManifestFormat {
+    Csv,
+    Json,
+}
+
+// 一条记录导入失败的原因
+#[derive(Debug, Clone, PartialEq)]
+enum ManifestError {
+    // CSV 引号不配对、字段数不对，或者 JSON 语法错误
+    Syntax(String),
+    MissingField(&'static str),
+    InvalidNumber(&'static str, String),
+    // JSON 字段的值是数组或对象
+    UnsupportedValue(String),
+    // JSON 数组里的元素不是对象
+    NotAnObject,
+    Weight(WeightError),
+    Package(PackageError),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManifestError::Syntax(message) => write!(f, "{message}"),
+            ManifestError::MissingField(name) => write!(f, "missing field {name}"),
+            ManifestError::InvalidNumber(name, value) => write!(f, "invalid {name} {value:?}"),
+            ManifestError::UnsupportedValue(name) => write!(f, "field {name} must be a string or a number"),
+            ManifestError::NotAnObject => write!(f, "expected an object"),
+            ManifestError::Weight(e) => write!(f, "{e}"),
+            ManifestError::Package(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for ManifestError {}
+
+// 带行号的错误，行号从 1 开始，JSON 记录的行号是它的 { 所在的行
+#[derive(Debug, Clone, PartialEq)]
+struct RowError {
+    line: usize,
+    error: ManifestError,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl Error for RowError {}
+
+// 导入结果：坏记录不会中断导入，收集到 errors 里，好记录照常导入
+#[derive(Debug, Default)]
+struct Manifest {
+    packages: Vec<Package>,
+    errors: Vec<RowError>,
+}
+
+impl Manifest {
+    // 整个文件读不下去（没有表头、JSON 语法错误）时返回 Err
+    fn read(text: &str, format: ManifestFormat) -> Result<Manifest, RowError> {
+        match format {
+            ManifestFormat::Csv => read_csv(text),
+            ManifestFormat::Json => read_json(text),
+        }
+    }
+
+    fn write(packages: &[Package], format: ManifestFormat) -> String {
+        match format {
+            ManifestFormat::Csv => {
+                let mut out = format!("{CSV_HEADER}\n");
+                for package in packages {
+                    out.push_str(&package.to_csv_row());
+                    out.push('\n');
+                }
+                out
+            }
+            ManifestFormat::Json if packages.is_empty() => String::from("[]\n"),
+            ManifestFormat::Json => {
+                let rows: Vec<String> = packages.iter().map(|package| format!("  {}", package.to_json())).collect();
+                format!("[\n{}\n]\n", rows.join(",\n"))
+            }
+        }
+    }
+
+    fn push(&mut self, line: usize, result: Result<Package, ManifestError>) {
+        match result {
+            Ok(package) => self.packages.push(package),
+            Err(error) => self.errors.push(RowError { line, error }),
+        }
+    }
+}
+
+impl Package {
+    // 国家写二字母代码，重量写成 "1200 g" 这种能原样解析回来的形式
+    // 语法：这些值里不会有逗号和引号，CSV 字段不需要加引号
+    fn to_csv_row(&self) -> String {
+        let sides = self.dimension_fields().unwrap_or_default();  // 语法：没有尺寸时是三个空字符串
+        format!("{},{},{},{}", self.sender_country.alpha2(), self.recipient_country.alpha2(), self.weight, sides.join(","))
+    }
+
+    fn to_json(&self) -> String {
+        let mut out = format!(
+            "{{\"sender\": \"{}\", \"recipient\": \"{}\", \"weight\": \"{}\"",  // 语法：{{ 输出一个 {
+            self.sender_country.alpha2(),
+            self.recipient_country.alpha2(),
+            self.weight
+        );
+        if let Some(sides) = self.dimension_fields() {
+            for (name, side) in DIMENSION_FIELDS.iter().zip(sides) {
+                out.push_str(&format!(", \"{name}\": {side}"));
+            }
+        }
+        out.push('}');
+        out
+    }
+
+    fn dimension_fields(&self) -> Option<[String; 3]> {
+        self.dimensions.map(|d| [d.length_um, d.width_um, d.height_um].map(format_millimetres))
+    }
+}
+
+// 微米写成毫米，去掉小数末尾的 0："254000" -> "254"，"25400" -> "25.4"
+fn format_millimetres(micrometres: i64) -> String {
+    let (mm, fraction) = (micrometres / 1_000, micrometres % 1_000);
+    if fraction == 0 {
+        return mm.to_string();
+    }
+    format!("{mm}.{}", format!("{fraction:03}").trim_end_matches('0'))
+}
+
+// 毫米数（最多 3 位小数）换成微米，格式不对或溢出时返回 None
+fn parse_millimetres(text: &str) -> Option<i64> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || fraction.len() > 3 || !digits(whole) || !digits(fraction) {
+        return None;
+    }
+    let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let fraction: i64 = format!("{fraction:0<3}").parse().ok()?;  // 语法：右侧补 0 到 3 位
+    whole.checked_mul(1_000)?.checked_add(fraction)
+}
+
+// 一条记录：(字段名, 值) 列表，CSV 和 JSON 都先转成这个再建 Package
+fn package_from_record(record: &[(String, String)]) -> Result<Package, ManifestError> {
+    let field = |name: &str| {
+        record.iter().find(|(key, _)| key == name).map(|(_, value)| value.trim()).filter(|value| !value.is_empty())
+    };
+    let weight = field("weight").ok_or(ManifestError::MissingField("weight"))?;
+    let weight = weight.parse::<Weight>().map_err(ManifestError::Weight)?;
+    // 语法：国家为空交给 try_new 报 EmptyCountry
+    let sender = field("sender").unwrap_or_default().to_string();
+    let recipient = field("recipient").unwrap_or_default().to_string();
+    let package = Package::try_new(sender, recipient, weight).map_err(ManifestError::Package)?;
+
+    let sides = DIMENSION_FIELDS.map(field);
+    if sides.iter().all(Option::is_none) {
+        return Ok(package);
+    }
+    let mut micrometres = [0; 3];
+    for ((name, side), um) in DIMENSION_FIELDS.into_iter().zip(sides).zip(&mut micrometres) {
+        let side = side.ok_or(ManifestError::MissingField(name))?;
+        *um = parse_millimetres(side).ok_or_else(|| ManifestError::InvalidNumber(name, side.to_string()))?;
+    }
+    let [length, width, height] = micrometres;
+    let dimensions = Dimensions::new(length, width, height, LengthUnit::Micrometre).map_err(ManifestError::Package)?;
+    Ok(package.with_dimensions(dimensions))
+}
+
+// 按 RFC 4180 拆一行 CSV：字段可以用双引号包起来，引号里的 "" 表示一个引号
+// 不支持引号里换行，清单里的字段用不到
+fn split_csv_line(line: &str) -> Result<Vec<String>, ManifestError> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();  // 语法：peekable 可以看下一个字符而不消耗它
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),  // 内存：take 把 field 换成空字符串，不复制
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(ManifestError::Syntax(String::from("unterminated quoted field")));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+// 第一行非空行是表头，列的顺序随意，多余的列忽略；空行跳过但计入行号
+fn read_csv(text: &str) -> Result<Manifest, RowError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line)).filter(|(_, line)| !line.trim().is_empty());
+    let Some((header_line, header)) = lines.next() else {
+        return Err(RowError { line: 1, error: ManifestError::Syntax(String::from("manifest is empty")) });
+    };
+    let header = header.trim_start_matches('\u{feff}');  // Excel 导出的 CSV 开头可能有 BOM
+    let columns: Vec<String> = split_csv_line(header)
+        .map_err(|error| RowError { line: header_line, error })?
+        .iter()
+        .map(|column| column.trim().to_ascii_lowercase())
+        .collect();
+    for required in ["sender", "recipient", "weight"] {
+        if !columns.iter().any(|column| column == required) {
+            return Err(RowError { line: header_line, error: ManifestError::MissingField(required) });
+        }
+    }
+
+    let mut manifest = Manifest::default();
+    for (line, text) in lines {
+        let record = split_csv_line(text).and_then(|fields| {
+            if fields.len() != columns.len() {
+                let message = format!("expected {} fields, found {}", columns.len(), fields.len());
+                return Err(ManifestError::Syntax(message));
+            }
+            Ok(columns.iter().cloned().zip(fields).collect::<Vec<_>>())
+        });
+        manifest.push(line, record.and_then(|record| package_from_record(&record)));
+    }
+    Ok(manifest)
+}
+
+// JSON 值，数字保留原文，交给字段自己解析
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+// 数组、对象最多嵌套的层数；每层递归占一段栈，不限制的话 "[[[[..." 就能把栈撑爆
+const MAX_JSON_DEPTH: usize = 128;
+
+// 递归下降解析器，边读边数行号
+struct JsonParser<'a> {  // 语法：生命周期 'a 表示解析器借用的文本至少和它活得一样久
+    text: &'a str,
+    pos: usize,
+    line: usize,
+    depth: usize,  // 当前所在的数组、对象层数
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: &str) -> RowError {
+        RowError { line: self.line, error: ManifestError::Syntax(message.to_string()) }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();  // 内存：pos 是字节下标，非 ASCII 字符占多个字节
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), RowError> {
+        self.skip_whitespace();
+        if self.bump() == Some(expected) { Ok(()) } else { Err(self.error(&format!("expected '{expected}'"))) }
+    }
+
+    // 逗号分隔、以 close 结尾的列表，每个元素由 item 读取
+    fn list(&mut self, close: char, mut item: impl FnMut(&mut Self) -> Result<(), RowError>) -> Result<(), RowError> {
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.bump();
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => continue,
+                Some(c) if c == close => return Ok(()),
+                _ => return Err(self.error(&format!("expected ',' or '{close}'"))),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, RowError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(open @ ('{' | '[')) => {
+                if self.depth == MAX_JSON_DEPTH {
+                    return Err(self.error("nesting too deep"));
+                }
+                self.bump();
+                self.depth += 1;
+                let value = if open == '{' { self.object() } else { self.array() };
+                self.depth -= 1;
+                value
+            }
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    // 开头的 { 已经读掉
+    fn object(&mut self) -> Result<Json, RowError> {
+        let mut fields = Vec::new();
+        self.list('}', |parser| {
+            parser.skip_whitespace();
+            if parser.peek() != Some('"') {
+                return Err(parser.error("expected a string key"));
+            }
+            let key = parser.string()?;
+            parser.expect(':')?;
+            fields.push((key, parser.value()?));
+            Ok(())
+        })?;
+        Ok(Json::Object(fields))
+    }
+
+    // 开头的 [ 已经读掉
+    fn array(&mut self) -> Result<Json, RowError> {
+        let mut items = Vec::new();
+        self.list(']', |parser| {
+            items.push(parser.value()?);
+            Ok(())
+        })?;
+        Ok(Json::Array(items))
+    }
+
+    // JSON 数字：-?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?，像 1-2e 这样的写法要报错
+    fn number(&mut self) -> Result<Json, RowError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.bump();
+        }
+        match self.peek() {
+            Some('0') => {
+                self.bump();
+            }
+            Some('1'..='9') => self.digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.peek() == Some('.') {
+            self.bump();
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.bump();
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.bump();
+            }
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+        // 数字后面紧跟的只能是分隔符，"012"、"1-2" 这类都不合法
+        if matches!(self.peek(), Some('0'..='9' | '-' | '+' | '.' | 'e' | 'E')) {
+            return Err(self.error("invalid number"));
+        }
+        Ok(Json::Number(self.text[start..self.pos].to_string()))
+    }
+
+    fn digits(&mut self) {
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.bump();
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, RowError> {
+        if self.text[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("expected a value"))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, RowError> {
+        self.bump();  // 开头的引号
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    let c = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    out.push(c);
+                }
+                Some(c) if c < ' ' => return Err(self.error("control character in string")),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    // \uXXXX，UTF-16 代理对要两个连在一起才是一个字符
+    fn unicode_escape(&mut self) -> Result<char, RowError> {
+        let mut code = self.hex4()?;
+        if (0xD800..0xDC00).contains(&code) {
+            if !self.text[self.pos..].starts_with("\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+        }
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))  // 语法：单独的低位代理不是合法的 char
+    }
+
+    fn hex4(&mut self) -> Result<u32, RowError> {
+        // 语法：get 在越界或不在字符边界上时返回 None，不会 panic
+        let digits = self.text.get(self.pos..self.pos + 4).filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()));
+        let digits = digits.ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("checked hex digits"))
+    }
+}
+
+// JSON 对象转成记录：null 当作没有这个字段，true/false 和数字按原文
+fn json_record(value: Json) -> Result<Vec<(String, String)>, ManifestError> {
+    let Json::Object(fields) = value else {
+        return Err(ManifestError::NotAnObject);
+    };
+    let mut record = Vec::new();
+    for (key, value) in fields {
+        let value = match value {
+            Json::Null => continue,
+            Json::Bool(b) => b.to_string(),
+            Json::Number(number) | Json::String(number) => number,
+            Json::Array(_) | Json::Object(_) => return Err(ManifestError::UnsupportedValue(key)),
+        };
+        record.push((key, value));
+    }
+    Ok(record)
+}
+
+// 顶层必须是对象数组；语法错误之后没法可靠地找到下一条记录，所以整个文件失败
+fn read_json(text: &str) -> Result<Manifest, RowError> {
+    let mut parser = JsonParser { text: text.trim_start_matches('\u{feff}'), pos: 0, line: 1, depth: 0 };
+    parser.expect('[')?;
+    let mut manifest = Manifest::default();
+    parser.list(']', |parser| {
+        parser.skip_whitespace();
+        let line = parser.line;
+        let value = parser.value()?;
+        manifest.push(line, json_record(value).and_then(|record| package_from_record(&record)));
+        Ok(())
+    })?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected text after the manifest"));
+    }
+    Ok(manifest)
+}
+
+// 包裹跟踪状态
+// 正常流程：Created -> LabelPrinted -> InTransit -> OutForDelivery -> Delivered
+// 派送失败会从 OutForDelivery 回到 InTransit；运输或派送途中都可能被退回（Returned）
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TrackingState {
+    Created,
+    LabelPrinted,
+    InTransit,
+    OutForDelivery,
+    Delivered,
+    Returned,
+}
+
+impl TrackingState {
+    // 允许的状态转换，其余一律拒绝
+    fn can_move_to(self, next: TrackingState) -> bool {
+        use TrackingState::*;  // 语法：在函数内导入枚举变体，省掉前缀
+        matches!(
+            (self, next),
+            (Created, LabelPrinted)
+                | (LabelPrinted, InTransit)
+                | (InTransit, InTransit)  // 经过中转站，同一状态下的新扫描
+                | (InTransit, OutForDelivery)
+                | (OutForDelivery, InTransit)  // 派送失败，回到网点
+                | (OutForDelivery, Delivered)
+                | (InTransit | OutForDelivery, Returned)
+        )
+    }
+
+    // 签收或退回之后不会再有新事件
+    fn is_final(self) -> bool {
+        matches!(self, TrackingState::Delivered | TrackingState::Returned)
+    }
+}
+
+impl fmt::Display for TrackingState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TrackingState::Created => "created",
+            TrackingState::LabelPrinted => "label printed",
+            TrackingState::InTransit => "in transit",
+            TrackingState::OutForDelivery => "out for delivery",
+            TrackingState::Delivered => "delivered",
+            TrackingState::Returned => "returned",
+        };
+        f.write_str(name)
+    }
+}
+
+// 一次扫描或状态变化
+#[derive(Debug, Clone, PartialEq)]
+struct TrackingEvent {
+    state: TrackingState,
+    at: SystemTime,  // 内存：SystemTime 在栈上，Linux 下是 16 字节的秒和纳秒
+    location: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TrackingError {
+    IllegalTransition { from: TrackingState, to: TrackingState },
+    // 新事件比上一个事件还早，多半是扫描枪时钟不对或者事件重复上报
+    OutOfOrder { previous: SystemTime, at: SystemTime },
+}
+
+impl fmt::Display for TrackingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrackingError::IllegalTransition { from, to } => write!(f, "package can not go from {from} to {to}"),
+            TrackingError::OutOfOrder { previous, at } => {
+                write!(f, "event at {:?} is earlier than the previous event at {:?}", at, previous)
+            }
+        }
+    }
+}
+
+impl Error for TrackingError {}
+
+// 跟踪记录：按时间顺序的事件列表，最后一个事件的状态就是当前状态
+#[derive(Debug, Clone, PartialEq)]
+struct Tracking {
+    events: Vec<TrackingEvent>,  // 编译器：new 一定放入 Created 事件，events 永远不为空
+}
+
+impl Tracking {
+    fn new(created_at: SystemTime, location: &str) -> Tracking {
+        let created = TrackingEvent { state: TrackingState::Created, at: created_at, location: location.to_string() };
+        Tracking { events: vec![created] }
+    }
+
+    fn last(&self) -> &TrackingEvent {
+        self.events.last().expect("tracking starts with a Created event")
+    }
+
+    fn state(&self) -> TrackingState {
+        self.last().state
+    }
+
+    // 记录新事件；非法转换或时间倒退时返回 Err，跟踪记录保持不变
+    fn advance(&mut self, next: TrackingState, at: SystemTime, location: &str) -> Result<(), TrackingError> {
+        let last = self.last();
+        if !last.state.can_move_to(next) {
+            return Err(TrackingError::IllegalTransition { from: last.state, to: next });
+        }
+        if at < last.at {
+            return Err(TrackingError::OutOfOrder { previous: last.at, at });
+        }
+        self.events.push(TrackingEvent { state: next, at, location: location.to_string() });
+        Ok(())
+    }
+
+    // 签收时间，没签收时是 None
+    fn delivered_at(&self) -> Option<SystemTime> {
+        let last = self.last();
+        (last.state == TrackingState::Delivered).then_some(last.at)  // 语法：bool::then_some 把 true 变成 Some
+    }
 }
 
-impl Package {  // 语法：为 Package 结构体实现方法
-    // 构造函数，创建 Package 实例
-    fn new(sender_country: String, recipient_country: String, weight_in_grams: i32) -> Package {
-        // 编译器：类型检查参数和返回值，检查 panic! 宏的有效性
-        // 内存：参数通过值传递，触发 String 所有权转移
-        if weight_in_grams <= 0 {
-            panic!("Can not ship a weightless package.");  // 语法： panic 宏终止程序
-                                                         // 内存：释放已分配的栈内存
+// 带跟踪记录的包裹
+#[derive(Debug)]
+struct TrackedPackage {
+    package: Package,
+    tracking: Tracking,
+}
+
+impl Package {
+    // 开始跟踪，包裹的所有权转移进 TrackedPackage
+    fn track(self, created_at: SystemTime, location: &str) -> TrackedPackage {
+        TrackedPackage { package: self, tracking: Tracking::new(created_at, location) }
+    }
+}
+
+// 运单号，格式参照万国邮联 S10：2 位业务代码 + 8 位序号 + 1 位校验码 + 2 位寄件国代码，比如 RR473124829GB
+#[derive(Debug, Clone, PartialEq)]
+struct TrackingNumber(String);
+
+#[derive(Debug, Clone, PartialEq)]
+enum LabelError {
+    // 业务代码必须是 2 个大写字母
+    InvalidService(String),
+    // 序号只有 8 位
+    SerialOutOfRange(u32),
+    // 扫描进来的运单号格式或校验码不对
+    InvalidTrackingNumber(String),
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LabelError::InvalidService(service) => write!(f, "service code {service:?} must be two letters A-Z"),
+            LabelError::SerialOutOfRange(serial) => write!(f, "serial {serial} has more than 8 digits"),
+            LabelError::InvalidTrackingNumber(text) => write!(f, "invalid tracking number {text:?}"),
+        }
+    }
+}
+
+impl Error for LabelError {}
+
+// S10 校验码：8 位序号分别乘以 8 6 4 2 3 5 9 7 求和，11 减去和除以 11 的余数；结果 10 记为 0，11 记为 5
+fn s10_check_digit(serial: u32) -> u32 {
+    const WEIGHTS: [u32; 8] = [8, 6, 4, 2, 3, 5, 9, 7];
+    let sum: u32 = format!("{serial:08}").bytes().zip(WEIGHTS).map(|(digit, weight)| (digit - b'0') as u32 * weight).sum();
+    match 11 - sum % 11 {
+        10 => 0,
+        11 => 5,
+        check => check,
+    }
+}
+
+impl TrackingNumber {
+    // 按序号生成运单号，序号一般来自数据库里的自增计数器
+    fn new(service: &str, serial: u32, origin: Country) -> Result<TrackingNumber, LabelError> {
+        if service.len() != 2 || !service.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(LabelError::InvalidService(service.to_string()));
+        }
+        if serial > 99_999_999 {
+            return Err(LabelError::SerialOutOfRange(serial));
+        }
+        let check = s10_check_digit(serial);
+        Ok(TrackingNumber(format!("{service}{serial:08}{check}{}", origin.alpha2())))
+    }
+
+    // 校验扫描或手工输入的运单号，忽略空格、不区分大小写
+    fn parse(text: &str) -> Result<TrackingNumber, LabelError> {
+        let invalid = || LabelError::InvalidTrackingNumber(text.to_string());
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+        // 语法：get 按字节切片，遇到非 ASCII 字符不在边界上时返回 None
+        let (Some(service), Some(serial), Some(check), Some(country)) =
+            (compact.get(..2), compact.get(2..10), compact.get(10..11), compact.get(11..))
+        else {
+            return Err(invalid());
+        };
+        if !serial.bytes().all(|b| b.is_ascii_digit()) || country.len() != 2 {
+            return Err(invalid());
+        }
+        let serial: u32 = serial.parse().map_err(|_| invalid())?;
+        let origin = Country::parse(country).filter(|c| c.alpha2() == country).ok_or_else(invalid)?;
+        let number = TrackingNumber::new(service, serial, origin).map_err(|_| invalid())?;
+        if number.0[10..11] != *check {
+            return Err(invalid());
+        }
+        Ok(number)
+    }
+}
+
+impl fmt::Display for TrackingNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Code128 的 107 个符号，每个符号是 3 条黑条和 3 个空白的宽度（单位：模块），最后一个是终止符（多一条黑条）
+// 下标 0..=102 是数据值，103/104/105 是 A/B/C 字符集的起始符
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213", "221312",
+    "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132", "221231", "213212",
+    "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211", "212123", "212321", "232121",
+    "111323", "131123", "131321", "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331",
+    "132131", "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131", "311123",
+    "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111", "111224", "111422", "121124",
+    "121421", "141122", "141221", "112214", "112412", "122114", "122411", "142112", "142211", "241211", "221114",
+    "413111", "241112", "134111", "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112",
+    "421211", "212141", "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+const CODE128_START_B: usize = 104;
+const CODE128_STOP: usize = 106;
+
+// 用 B 字符集编码（可打印 ASCII），返回每个模块是否为黑条；有不可打印字符时返回 None
+// 校验值 = (起始符 + 每个字符的值 * 它的位置) % 103
+fn code128(text: &str) -> Option<Vec<bool>> {
+    let mut symbols = vec![CODE128_START_B];
+    for c in text.chars() {
+        if !(' '..='~').contains(&c) {
+            return None;
+        }
+        symbols.push(c as usize - 32);  // 语法：B 字符集里字符的值是 ASCII 码减 32
+    }
+    let checksum = symbols.iter().enumerate().map(|(i, &value)| i.max(1) * value).sum::<usize>() % 103;
+    symbols.push(checksum);
+    symbols.push(CODE128_STOP);
+
+    let mut modules = Vec::new();
+    for symbol in symbols {
+        for (i, width) in CODE128_PATTERNS[symbol].bytes().enumerate() {
+            // 偶数位是黑条，奇数位是空白
+            modules.extend(std::iter::repeat_n(i % 2 == 0, (width - b'0') as usize));
+        }
+    }
+    Some(modules)
+}
+
+// 一张面单：寄件国、收件国、重量、运费和运单号条码
+#[derive(Debug)]
+struct Label<'a> {
+    package: &'a Package,  // 语法：借用包裹，面单不需要拥有它
+    tracking_number: TrackingNumber,
+    fee: Money,
+}
+
+impl<'a> Label<'a> {
+    fn new(package: &'a Package, tracking_number: TrackingNumber, fee: Money) -> Label<'a> {
+        Label { package, tracking_number, fee }
+    }
+
+    // 两种格式共用的文字行
+    fn lines(&self) -> [String; 4] {
+        let country = |c: Country| format!("{} ({})", c.name(), c.alpha2());
+        [
+            format!("FROM: {}", country(self.package.sender_country)),
+            format!("TO: {}", country(self.package.recipient_country)),
+            format!("WEIGHT: {}", self.package.weight),
+            format!("FEE: {}", self.fee),
+        ]
+    }
+
+    // Zebra 热敏打印机的 ZPL 指令，条码由打印机用 ^BC（Code128）生成
+    fn to_zpl(&self) -> String {
+        // ^ 和 ~ 是 ZPL 的控制字符，不能出现在字段内容里
+        let field = |text: &str| text.replace(['^', '~'], " ");
+        let mut zpl = String::from("^XA\n^CI28\n");  // ^CI28：字段内容按 UTF-8 解释
+        for (i, line) in self.lines().iter().enumerate() {
+            zpl.push_str(&format!("^FO50,{}^A0N,30,30^FD{}^FS\n", 50 + i * 40, field(line)));
+        }
+        zpl.push_str(&format!("^FO50,230^BY2^BCN,120,Y,N,N^FD{}^FS\n^XZ\n", self.tracking_number));
+        zpl
+    }
+
+    // 独立的 SVG 文件，条码每个模块 2 像素宽，两侧各留 10 个模块的空白区
+    fn to_svg(&self) -> String {
+        const MODULE: usize = 2;
+        const QUIET: usize = 10 * MODULE;
+        let modules = code128(&self.tracking_number.0).expect("tracking numbers are ASCII");
+        let width = (modules.len() * MODULE + 2 * QUIET).max(400);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"260\" viewBox=\"0 0 {width} 260\">\n\
+             <rect width=\"{width}\" height=\"260\" fill=\"white\"/>\n"
+        );
+        for (i, line) in self.lines().iter().enumerate() {
+            svg.push_str(&format!(
+                "<text x=\"{QUIET}\" y=\"{}\" font-family=\"monospace\" font-size=\"18\">{}</text>\n",
+                30 + i * 25,
+                xml_escape(line)
+            ));
+        }
+        // 连续的黑色模块合并成一个矩形
+        svg.push_str("<g fill=\"black\">\n");
+        let mut i = 0;
+        while i < modules.len() {
+            let run = modules[i..].iter().take_while(|&&black| black == modules[i]).count();
+            if modules[i] {
+                svg.push_str(&format!("<rect x=\"{}\" y=\"130\" width=\"{}\" height=\"80\"/>\n", QUIET + i * MODULE, run * MODULE));
+            }
+            i += run;
+        }
+        svg.push_str("</g>\n");
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"235\" font-family=\"monospace\" font-size=\"18\" text-anchor=\"middle\">{}</text>\n</svg>\n",
+            width / 2,
+            self.tracking_number
+        ));
+        svg
+    }
+}
+
+// 国家名里可能有 & 和 '，比如 "Côte d'Ivoire"
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());  // 内存：一次分配够大部分情况
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ISO 4217 货币代码（不含黄金、白银等贵金属和测试用代码）
+// 语法：concat! 在编译期把多个字面量拼成一个 &'static str
+const CURRENCY_CODES: &str = concat!(
+    "AED AFN ALL AMD ANG AOA ARS AUD AWG AZN BAM BBD BDT BGN BHD BIF BMD BND BOB BOV BRL BSD BTN BWP BYN ",
+    "BZD CAD CDF CHE CHF CHW CLF CLP CNY COP COU CRC CUC CUP CVE CZK DJF DKK DOP DZD EGP ERN ETB EUR FJD ",
+    "FKP GBP GEL GHS GIP GMD GNF GTQ GYD HKD HNL HRK HTG HUF IDR ILS INR IQD IRR ISK JMD JOD JPY KES KGS ",
+    "KHR KMF KPW KRW KWD KYD KZT LAK LBP LKR LRD LSL LYD MAD MDL MGA MKD MMK MNT MOP MRU MUR MVR MWK MXN ",
+    "MXV MYR MZN NAD NGN NIO NOK NPR NZD OMR PAB PEN PGK PHP PKR PLN PYG QAR RON RSD RUB RWF SAR SBD SCR ",
+    "SDG SEK SGD SHP SLE SLL SOS SRD SSP STN SVC SYP SZL THB TJS TMT TND TOP TRY TTD TWD TZS UAH UGX USD ",
+    "USN UYI UYU UYW UZS VED VES VND VUV WST XAF XCD XOF XPF YER ZAR ZMW ZWL",
+);
+// 小数位不是 2 的货币：日元、韩元没有辅币单位，科威特第纳尔等是 3 位
+const ZERO_DECIMAL_CURRENCIES: &str = "BIF CLP DJF GNF ISK JPY KMF KRW PYG RWF UGX UYI VND VUV XAF XOF XPF";
+const THREE_DECIMAL_CURRENCIES: &str = "BHD IQD JOD KWD LYD OMR TND";
+const FOUR_DECIMAL_CURRENCIES: &str = "CLF UYW";
+
+// 货币代码，比如 EUR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]  // 编译器：Copy 之后按值传递也不会移动所有权
+struct Currency([u8; 3]);  // 语法：元组结构体
+                           // 内存：3 个字节直接放在栈上，不需要堆分配
+
+impl Currency {
+    // 不区分大小写，只接受 ISO 4217 里的代码
+    fn new(code: &str) -> Result<Currency, MoneyError> {
+        let upper = code.trim().to_ascii_uppercase();
+        if upper.len() != 3 || !CURRENCY_CODES.split(' ').any(|known| known == upper) {
+            return Err(MoneyError::UnknownCurrency(code.to_string()));
+        }
+        let bytes = upper.as_bytes();
+        Ok(Currency([bytes[0], bytes[1], bytes[2]]))
+    }
+
+    fn code(&self) -> &str {
+        // 编译器：new 只放进 ASCII 大写字母，这里的 UTF-8 转换不会失败
+        std::str::from_utf8(&self.0).expect("currency codes are ASCII")
+    }
+
+    // 最小单位的小数位数：EUR 是 2（1 欧元 = 100 分），JPY 是 0
+    fn decimals(&self) -> u32 {
+        let code = self.code();
+        let listed = |list: &str| list.split(' ').any(|listed| listed == code);
+        if listed(ZERO_DECIMAL_CURRENCIES) {
+            0
+        } else if listed(THREE_DECIMAL_CURRENCIES) {
+            3
+        } else if listed(FOUR_DECIMAL_CURRENCIES) {
+            4
+        } else {
+            2
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+// 除不尽时怎么取整
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rounding {
+    // 四舍五入，0.5 远离 0
+    HalfUp,
+    // 银行家舍入，0.5 取最近的偶数
+    HalfEven,
+    // 直接截断，朝 0 取整
+    Down,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MoneyError {
+    UnknownCurrency(String),
+    // 两个金额币种不同，不能直接相加或比较
+    CurrencyMismatch(Currency, Currency),
+    // 结果超出 i64 范围
+    Overflow,
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoneyError::UnknownCurrency(code) => write!(f, "unknown currency {code:?}"),
+            MoneyError::CurrencyMismatch(a, b) => write!(f, "can not combine {a} with {b}"),
+            MoneyError::Overflow => write!(f, "amount out of range"),
+        }
+    }
+}
+
+impl Error for MoneyError {}
+
+// 金额：以最小货币单位（分）存储的整数加币种，不用浮点数，避免 0.1 + 0.2 这样的误差
+// 所有运算都检查溢出，release 模式下也不会悄悄回绕成负数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Money {
+    minor: i64,  // 内存：8 字节，足够表示 9 * 10^16 分
+    currency: Currency,
+}
+
+impl Money {
+    fn new(minor: i64, currency: Currency) -> Money {
+        Money { minor, currency }
+    }
+
+    fn zero(currency: Currency) -> Money {
+        Money::new(0, currency)
+    }
+
+    fn same_currency(self, other: Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
         } else {
-            // 语法：结构体初始化语法（字段名缩写）
-            // 内存：在栈上创建 Package 实例，String 数据保留在堆上
-            Package {
-                sender_country,
-                recipient_country,
-                weight_in_grams,
+            Err(MoneyError::CurrencyMismatch(self.currency, other.currency))
+        }
+    }
+
+    fn checked_add(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let minor = self.minor.checked_add(other.minor).ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(minor, self.currency))
+    }
+
+    fn checked_sub(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let minor = self.minor.checked_sub(other.minor).ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(minor, self.currency))
+    }
+
+    fn checked_mul(self, factor: i64) -> Result<Money, MoneyError> {
+        let minor = self.minor.checked_mul(factor).ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(minor, self.currency))
+    }
+
+    // 乘以 numerator / denominator 后按 rounding 取整，百分比附加费、折扣都用它
+    fn mul_ratio(self, numerator: i64, denominator: i64, rounding: Rounding) -> Result<Money, MoneyError> {
+        assert!(denominator > 0, "denominator must be positive");
+        // 内存：中间结果用 i128，两个 i64 相乘不会溢出
+        let product = self.minor as i128 * numerator as i128;
+        let (quotient, remainder) = (product / denominator as i128, product % denominator as i128);
+        let twice = remainder.abs() * 2;
+        let away_from_zero = match rounding {
+            Rounding::HalfUp => twice >= denominator as i128,
+            Rounding::HalfEven => twice > denominator as i128 || (twice == denominator as i128 && quotient % 2 != 0),
+            Rounding::Down => false,
+        };
+        let rounded = if away_from_zero { quotient + product.signum() } else { quotient };
+        let minor = i64::try_from(rounded).map_err(|_| MoneyError::Overflow)?;
+        Ok(Money::new(minor, self.currency))
+    }
+
+    fn max(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        Ok(if other.minor > self.minor { other } else { self })
+    }
+}
+
+// 按币种的小数位显示，比如 "45.00 EUR"、"-0.05 USD"、"1200 JPY"
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decimals = self.currency.decimals();
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let abs = self.minor.unsigned_abs();  // 语法：i64::MIN 取绝对值不会溢出
+        if decimals == 0 {
+            return write!(f, "{sign}{abs} {}", self.currency);
+        }
+        let scale = 10u64.pow(decimals);
+        write!(f, "{sign}{}.{:0width$} {}", abs / scale, abs % scale, self.currency, width = decimals as usize)
+    }
+}
+
+// 运费区域：国内件和国际件分别定价
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Domestic,
+    International,
+}
+
+// 一个重量档：不超过 up_to 的包裹收 base + 每克 per_gram，不足 1 克按 1 克算
+// 承运商按档一口价时 per_gram 为 0，按重量计价时 base 为 0
+#[derive(Debug, Clone, PartialEq)]
+struct Bracket {
+    up_to: Weight,
+    base: Money,
+    per_gram: Money,
+}
+
+// 一个区域的价格：重量档按 up_to 从小到大排列，再加一个最低收费
+#[derive(Debug, Clone, PartialEq)]
+struct ZoneRate {
+    zone: Zone,
+    brackets: Vec<Bracket>,  // 内存：Vec 在堆上存放各档，栈上只有 24 字节的指针/长度/容量
+    minimum: Money,
+}
+
+// 运价表：各区域的重量档、燃油附加费和国际件附加费
+#[derive(Debug, Clone, PartialEq)]
+struct Tariff {
+    rates: Vec<ZoneRate>,
+    // 燃油附加费，单位万分之一（1250 表示 12.50%），按运费加国际附加费之和计算
+    fuel_surcharge_basis_points: i64,
+    // 国际件每件固定加收的费用
+    international_surcharge: Money,
+    // 燃油附加费除不尽时的取整方式
+    rounding: Rounding,
+    // 体积重系数（每公斤立方厘米数），按实重和体积重中较大的计费
+    volumetric_divisor: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TariffError {
+    // 运价表里没有这个区域的价格
+    NoRate(Zone),
+    // 比最重的一档还重
+    NoBracket { weight: Weight, max: Weight },
+    // 金额溢出，或者运价表里混用了不同币种
+    Money(MoneyError),
+    // 体积重系数不是正数，算不出计费重量
+    InvalidVolumetricDivisor(i64),
+}
+
+impl fmt::Display for TariffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TariffError::NoRate(zone) => write!(f, "tariff has no rate for {zone:?} packages"),
+            TariffError::NoBracket { weight, max } => {
+                write!(f, "no weight bracket for {weight} (tariff goes up to {max})")
+            }
+            TariffError::Money(e) => write!(f, "{e}"),
+            TariffError::InvalidVolumetricDivisor(divisor) => {
+                write!(f, "volumetric divisor must be positive, got {divisor}")
             }
         }
     }
+}
 
-    // 判断是否为国际包裹
-    fn is_international(&self) -> bool {  // 语法：借用 self 的不可变引用
-                                        // 编译器：确保引用有效性，检查字符串比较
-                                        // 内存：通过引用访问字段，不移动数据
-        self.sender_country != self.recipient_country  // 语法：字符串内容比较
-                                                    // 编译器：生成字符串比较的字节码
-                                                    // 内存：比较两个 String 的堆数据
+impl Error for TariffError {}
+
+// 语法：实现 From 之后，? 会自动把 MoneyError 转换成 TariffError
+impl From<MoneyError> for TariffError {
+    fn from(e: MoneyError) -> TariffError {
+        TariffError::Money(e)
+    }
+}
+
+impl Tariff {
+    // 只按重量计价、不分区域、没有附加费和最低收费的运价表，相当于原来的 weight * cents_per_gram
+    fn flat(per_gram: Money) -> Tariff {
+        let zero = Money::zero(per_gram.currency);
+        let rate = |zone| ZoneRate {
+            zone,
+            brackets: vec![Bracket { up_to: MAX_WEIGHT, base: zero, per_gram }],
+            minimum: zero,
+        };
+        Tariff {
+            rates: vec![rate(Zone::Domestic), rate(Zone::International)],
+            fuel_surcharge_basis_points: 0,
+            international_surcharge: zero,
+            rounding: Rounding::HalfUp,
+            volumetric_divisor: DEFAULT_VOLUMETRIC_DIVISOR,
+        }
     }
 
-    // 计算运输费用
-    fn get_fees(&self, cents_per_gram: i32) -> i32 {  // 语法：参数和返回值均为 i32
-                                                    // 编译器：检查整数运算溢出（debug模式）
-                                                    // 内存：栈上进行整数乘法运算
-        self.weight_in_grams * cents_per_gram  // 语法：整数乘法
-                                           // 内存：直接操作栈上的 i32 数据
+    // 计算顺序：重量档价格 -> 加国际附加费 -> 加燃油附加费（按 rounding 取整）-> 不低于最低收费
+    fn fee(&self, weight: Weight, zone: Zone) -> Result<Money, TariffError> {
+        let rate = self.rates.iter().find(|rate| rate.zone == zone).ok_or(TariffError::NoRate(zone))?;
+        let bracket = rate
+            .brackets
+            .iter()
+            .find(|bracket| weight <= bracket.up_to)  // 语法：找到第一个装得下的档
+            .ok_or(TariffError::NoBracket {
+                weight,
+                max: rate.brackets.last().map_or(Weight::grams(0), |bracket| bracket.up_to),
+            })?;
+
+        let mut fee = bracket.per_gram.checked_mul(weight.ceil_grams())?.checked_add(bracket.base)?;
+        if zone == Zone::International {
+            fee = fee.checked_add(self.international_surcharge)?;
+        }
+        let fuel = fee.mul_ratio(self.fuel_surcharge_basis_points, 10_000, self.rounding)?;
+        Ok(fee.checked_add(fuel)?.max(rate.minimum)?)
     }
 }
+
 #[cfg(test)]  // 编译器：仅在测试模式下编译此模块
 mod tests {    // 语法：定义模块
     use super::*;  // 语法：导入上层模块的内容
@@ -57,7 +1904,7 @@ mod tests {    // 语法：定义模块
         let recipient_country = String::from("Austria");
 
         // 内存：调用 new 方法时，String 所有权转移到 Package 实例
-        Package::new(sender_country, recipient_country, -2210);
+        Package::new(sender_country, recipient_country, Weight::grams(-2210));
     }
 
     #[test]
@@ -66,7 +1913,7 @@ mod tests {    // 语法：定义模块
         let recipient_country = String::from("Russia");
 
         // 内存：Package 实例在栈上，包含指向堆上 String 的指针
-        let package = Package::new(sender_country, recipient_country, 1200);
+        let package = Package::new(sender_country, recipient_country, Weight::grams(1200));
 
         // 编译器：检查布尔值断言
         assert!(package.is_international());  // 语法：断言表达式为 true
@@ -80,7 +1927,7 @@ mod tests {    // 语法：定义模块
         // 内存：堆上创建新的字符串数据，栈上复制胖指针
         let recipient_country = sender_country.clone();
 
-        let package = Package::new(sender_country, recipient_country, 1200);
+        let package = Package::new(sender_country, recipient_country, Weight::grams(1200));
 
         assert!(!package.is_international());
     }
@@ -91,11 +1938,568 @@ mod tests {    // 语法：定义模块
         let recipient_country = String::from("Spain");
 
         let cents_per_gram = 3;  // 内存：栈上存储 i32 数据
+        let eur = Currency::new("EUR").unwrap();
 
-        let package = Package::new(sender_country, recipient_country, 1500);
+        let package = Package::new(sender_country, recipient_country, Weight::grams(1500));
 
         // 编译器：检查整数相等性断言
-        assert_eq!(package.get_fees(cents_per_gram), 4500);  // 语法：断言两值相等
-        assert_eq!(package.get_fees(cents_per_gram * 2), 9000);
+        assert_eq!(package.get_fees(&Tariff::flat(Money::new(cents_per_gram, eur))), Ok(Money::new(4500, eur)));  // 语法：断言两值相等
+        assert_eq!(package.get_fees(&Tariff::flat(Money::new(cents_per_gram * 2, eur))), Ok(Money::new(9000, eur)));
+    }
+
+    fn eur(minor: i64) -> Money {
+        Money::new(minor, Currency::new("EUR").unwrap())
+    }
+
+    // 国内件按档一口价，国际件按档加每克计价，另有燃油附加费和最低收费
+    fn carrier_tariff() -> Tariff {
+        let bracket = |grams, base, per_gram| Bracket { up_to: Weight::grams(grams), base: eur(base), per_gram: eur(per_gram) };
+        Tariff {
+            rates: vec![
+                ZoneRate {
+                    zone: Zone::Domestic,
+                    brackets: vec![bracket(1_000, 450, 0), bracket(5_000, 790, 0), bracket(30_000, 1_490, 0)],
+                    minimum: eur(500),
+                },
+                ZoneRate {
+                    zone: Zone::International,
+                    brackets: vec![bracket(2_000, 1_500, 0), bracket(20_000, 1_000, 1)],
+                    minimum: eur(0),
+                },
+            ],
+            fuel_surcharge_basis_points: 1_250,
+            international_surcharge: eur(300),
+            rounding: Rounding::HalfUp,
+            volumetric_divisor: DEFAULT_VOLUMETRIC_DIVISOR,
+        }
+    }
+
+    #[test]
+    fn tariff_uses_weight_brackets_and_minimum() {
+        let tariff = carrier_tariff();
+        let domestic = |weight| Package::new(String::from("Spain"), String::from("Spain"), Weight::grams(weight)).get_fees(&tariff);
+        // 450 低于最低收费 500，燃油附加费在最低收费之前计算：450 * 1.125 = 506.25 -> 506
+        assert_eq!(domestic(1_000), Ok(eur(506)));
+        // 790 * 1.125 = 888.75 -> 889
+        assert_eq!(domestic(1_001), Ok(eur(889)));
+        assert_eq!(domestic(30_000), Ok(eur(1_676)));
+    }
+
+    #[test]
+    fn tariff_adds_international_surcharges() {
+        let tariff = carrier_tariff();
+        let international = |weight| Package::new(String::from("Spain"), String::from("Canada"), Weight::grams(weight)).get_fees(&tariff);
+        // (1500 + 300) * 1.125 = 2025
+        assert_eq!(international(2_000), Ok(eur(2_025)));
+        // (1000 + 2500 + 300) * 1.125 = 4275
+        assert_eq!(international(2_500), Ok(eur(4_275)));
+        assert_eq!(international(25_000), Err(TariffError::NoBracket { weight: Weight::grams(25_000), max: Weight::grams(20_000) }));
+    }
+
+    #[test]
+    fn tariff_reports_missing_zone_overflow_and_mixed_currencies() {
+        let mut tariff = carrier_tariff();
+        tariff.rates.retain(|rate| rate.zone == Zone::Domestic);  // 语法：retain 原地删除不满足条件的元素
+        let package = Package::new(String::from("Spain"), String::from("Canada"), Weight::grams(30_000));
+        assert_eq!(package.get_fees(&tariff), Err(TariffError::NoRate(Zone::International)));
+        // 原来 i32 相乘在 release 模式下会回绕成负数，现在报错
+        assert_eq!(package.get_fees(&Tariff::flat(eur(i64::MAX / 1_000))), Err(TariffError::Money(MoneyError::Overflow)));
+
+        let usd = Currency::new("usd").unwrap();
+        let mut tariff = carrier_tariff();
+        tariff.international_surcharge = Money::new(300, usd);
+        let package = Package::new(String::from("Spain"), String::from("Canada"), Weight::grams(100));
+        assert_eq!(
+            package.get_fees(&tariff),
+            Err(TariffError::Money(MoneyError::CurrencyMismatch(Currency::new("EUR").unwrap(), usd)))
+        );
+    }
+
+    #[test]
+    fn currency_codes_are_validated() {
+        assert_eq!(Currency::new(" jpy ").map(|c| c.to_string()), Ok(String::from("JPY")));
+        assert_eq!(Currency::new("EURO"), Err(MoneyError::UnknownCurrency(String::from("EURO"))));
+        assert_eq!(Currency::new("XAU"), Err(MoneyError::UnknownCurrency(String::from("XAU"))));
+    }
+
+    #[test]
+    fn money_displays_with_currency_decimals() {
+        assert_eq!(eur(4500).to_string(), "45.00 EUR");
+        assert_eq!(eur(-5).to_string(), "-0.05 EUR");
+        assert_eq!(Money::new(1200, Currency::new("JPY").unwrap()).to_string(), "1200 JPY");
+        assert_eq!(Money::new(1234, Currency::new("KWD").unwrap()).to_string(), "1.234 KWD");
+        assert_eq!(Money::new(i64::MIN, Currency::new("EUR").unwrap()).to_string(), "-92233720368547758.08 EUR");
+    }
+
+    #[test]
+    fn money_arithmetic_is_checked() {
+        assert_eq!(eur(150).checked_add(eur(50)), Ok(eur(200)));
+        assert_eq!(eur(150).checked_sub(eur(200)), Ok(eur(-50)));
+        assert_eq!(eur(i64::MAX).checked_add(eur(1)), Err(MoneyError::Overflow));
+        assert_eq!(eur(i64::MIN).checked_sub(eur(1)), Err(MoneyError::Overflow));
+        assert_eq!(eur(i64::MAX / 2).checked_mul(3), Err(MoneyError::Overflow));
+        let usd = Money::new(1, Currency::new("USD").unwrap());
+        assert_eq!(eur(1).checked_add(usd), Err(MoneyError::CurrencyMismatch(eur(1).currency, usd.currency)));
+        assert_eq!(eur(1).max(usd), Err(MoneyError::CurrencyMismatch(eur(1).currency, usd.currency)));
+    }
+
+    #[test]
+    fn money_rounding_rules() {
+        // 25 * 50% = 12.5，35 * 50% = 17.5
+        let half = |minor, rounding| eur(minor).mul_ratio(1, 2, rounding).unwrap();
+        assert_eq!(half(25, Rounding::HalfUp), eur(13));
+        assert_eq!(half(25, Rounding::HalfEven), eur(12));
+        assert_eq!(half(35, Rounding::HalfEven), eur(18));
+        assert_eq!(half(25, Rounding::Down), eur(12));
+        // 负数对称：-12.5 四舍五入是 -13，截断是 -12
+        assert_eq!(half(-25, Rounding::HalfUp), eur(-13));
+        assert_eq!(half(-25, Rounding::Down), eur(-12));
+        // 12.50% 燃油附加费：333 * 0.125 = 41.625 -> 42
+        assert_eq!(eur(333).mul_ratio(1_250, 10_000, Rounding::HalfUp), Ok(eur(42)));
+        assert_eq!(eur(i64::MAX).mul_ratio(3, 2, Rounding::HalfUp), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn trade_zones_classify_routes() {
+        let zones = TradeZones::europe();
+        let package = |from: &str, to: &str| Package::new(from.to_string(), to.to_string(), Weight::grams(1200));
+
+        assert_eq!(package("Spain", "ES").route(&zones), Route::Domestic);
+        assert_eq!(package("Spain", "Austria").route(&zones), Route::IntraZone);
+        assert_eq!(package("Norway", "Iceland").route(&zones), Route::IntraZone);
+        assert_eq!(package("Spain", "Russia").route(&zones), Route::ExtraZone);
+        // 挪威不是欧盟成员，EU 和 EFTA 之间算区外
+        assert_eq!(package("Spain", "Norway").route(&zones), Route::ExtraZone);
+    }
+
+    #[test]
+    fn customs_is_required_outside_customs_unions() {
+        let mut zones = TradeZones::europe();
+        let package = |from: &str, to: &str| Package::new(from.to_string(), to.to_string(), Weight::grams(1200));
+
+        assert!(!package("Spain", "Spain").requires_customs(&zones));
+        assert!(!package("Spain", "Austria").requires_customs(&zones));
+        // EFTA 只是自由贸易区，成员之间仍要报关
+        assert!(package("Norway", "Switzerland").requires_customs(&zones));
+        assert!(package("Spain", "Russia").requires_customs(&zones));
+
+        // 配置可以修改：加入一个包含土耳其的关税同盟
+        let customs_union = vec![Country::parse("TR").unwrap(), Country::parse("DE").unwrap()];
+        zones.zones.push(TradeZone::new("EU-Turkey", customs_union, true));
+        assert_eq!(package("Turkey", "Germany").route(&zones), Route::IntraZone);
+        assert!(!package("Turkey", "Germany").requires_customs(&zones));
+        assert!(package("Turkey", "Spain").requires_customs(&zones));
+    }
+
+    #[test]
+    fn volumetric_weight_uses_units_and_rounds_up() {
+        let box_cm = Dimensions::new(40, 30, 20, LengthUnit::Centimetre).unwrap();
+        // 24000 cm³ / 5000 = 4.8 kg
+        assert_eq!(box_cm.volumetric_weight(5_000), Some(Weight::grams(4_800)));
+        assert_eq!(box_cm.volumetric_weight(0), None);
+        assert_eq!(box_cm.volumetric_weight(-5_000), None);
+        assert_eq!(Dimensions::new(400, 300, 200, LengthUnit::Millimetre), Ok(box_cm));
+        // 10 x 10 x 10 英寸 = 16387.064 cm³，/ 6000 = 2731.177333... g，纳克向上取整，计费时按 2732 g
+        let box_in = Dimensions::new(10, 10, 10, LengthUnit::Inch).unwrap();
+        assert_eq!(box_in.volumetric_weight(6_000).unwrap().to_string(), "2731.177333334 g");
+        assert_eq!(box_in.volumetric_weight(6_000).unwrap().ceil_grams(), 2_732);
+        assert_eq!(Dimensions::new(10, 0, 10, LengthUnit::Inch), Err(PackageError::NonPositiveDimension(0)));
+        assert_eq!(Dimensions::new(i64::MAX, 1, 1, LengthUnit::Inch), Err(PackageError::OversizedDimension(i64::MAX)));
+    }
+
+    #[test]
+    fn shipment_sums_chargeable_weight_per_parcel() {
+        let package = |weight| Package::new(String::from("Spain"), String::from("Austria"), Weight::grams(weight));
+        let mut shipment = Shipment::new();
+        // 大而轻：按体积重 4800 g 计费
+        shipment.add(package(1_000).with_dimensions(Dimensions::new(40, 30, 20, LengthUnit::Centimetre).unwrap()));
+        // 小而重：按实重计费
+        shipment.add(package(3_000).with_dimensions(Dimensions::new(10, 10, 10, LengthUnit::Centimetre).unwrap()));
+        // 没有尺寸
+        shipment.add(package(500));
+
+        assert_eq!(shipment.chargeable_weights(5_000), Some(vec![Weight::grams(4_800), Weight::grams(3_000), Weight::grams(500)]));
+        assert_eq!(shipment.total_weight(), Weight::grams(4_500));
+        assert_eq!(shipment.total_chargeable_weight(5_000), Some(Weight::grams(8_300)));
+        assert_eq!(shipment.total_chargeable_weight(0), None);
+    }
+
+    #[test]
+    fn fees_are_charged_on_volumetric_weight() {
+        let eur = Currency::new("EUR").unwrap();
+        let package = Package::new(String::from("Spain"), String::from("Spain"), Weight::grams(1_000))
+            .with_dimensions(Dimensions::new(40, 30, 20, LengthUnit::Centimetre).unwrap());
+        assert_eq!(package.get_fees(&Tariff::flat(Money::new(1, eur))), Ok(Money::new(4_800, eur)));
+        // 体积重超过运价表最重一档
+        let huge = package.with_dimensions(Dimensions::new(100, 100, 100, LengthUnit::Centimetre).unwrap());
+        assert_eq!(
+            huge.get_fees(&Tariff::flat(Money::new(1, eur))),
+            Err(TariffError::NoBracket { weight: Weight::grams(200_000), max: MAX_WEIGHT })
+        );
+
+        // 系数配错成 0：返回错误而不是 panic，没量尺寸的包裹也一样
+        let broken = Tariff { volumetric_divisor: 0, ..Tariff::flat(Money::new(1, eur)) };
+        assert_eq!(huge.get_fees(&broken), Err(TariffError::InvalidVolumetricDivisor(0)));
+        let plain = Package::new(String::from("Spain"), String::from("Spain"), Weight::grams(1_000));
+        assert_eq!(plain.get_fees(&broken), Err(TariffError::InvalidVolumetricDivisor(0)));
+        assert_eq!(TariffError::InvalidVolumetricDivisor(0).to_string(), "volumetric divisor must be positive, got 0");
+    }
+
+    #[test]
+    fn weight_units_convert_losslessly() {
+        let pound = Weight::new(1, WeightUnit::Pound).unwrap();
+        assert_eq!(Weight::new(16, WeightUnit::Ounce), Ok(pound));
+        assert_eq!(pound.to_string(), "453.59237 g");
+        assert_eq!(pound.in_unit(WeightUnit::Ounce), Some(16));
+        assert_eq!(pound.in_unit(WeightUnit::Gram), None);
+        assert_eq!(Weight::new(2, WeightUnit::Kilogram).unwrap().in_unit(WeightUnit::Gram), Some(2_000));
+        assert_eq!(Weight::new(i64::MAX, WeightUnit::Gram), Err(WeightError::Overflow));
+        // 计费时不足 1 克按 1 克算
+        assert_eq!(pound.ceil_grams(), 454);
+    }
+
+    #[test]
+    fn weight_parses_amounts_with_units() {
+        let parse = |text: &str| text.parse::<Weight>();
+        assert_eq!(parse("1.2 kg"), Ok(Weight::grams(1_200)));
+        assert_eq!(parse(" 1200g "), Ok(Weight::grams(1_200)));
+        assert_eq!(parse("3 LBS"), Weight::new(3, WeightUnit::Pound));
+        assert_eq!(parse(".5 pound"), Weight::new(8, WeightUnit::Ounce));
+        assert_eq!(parse("0.1 oz").map(|w| w.to_string()), Ok(String::from("2.834952313 g")));
+        assert_eq!(parse("1200"), Err(WeightError::MissingUnit));
+        assert_eq!(parse("1.2 stone"), Err(WeightError::UnknownUnit(String::from("stone"))));
+        assert_eq!(parse("1.2.3 kg"), Err(WeightError::InvalidNumber(String::from("1.2.3 kg"))));
+        assert_eq!(parse("-1 kg"), Err(WeightError::InvalidNumber(String::from("-1 kg"))));
+        assert_eq!(parse("99999999999999999999 g"), Err(WeightError::Overflow));
+    }
+
+    #[test]
+    fn fees_round_partial_grams_up() {
+        let eur = Currency::new("EUR").unwrap();
+        let package = Package::new(String::from("US"), String::from("US"), Weight::new(1, WeightUnit::Pound).unwrap());
+        assert_eq!(package.get_fees(&Tariff::flat(Money::new(1, eur))), Ok(Money::new(454, eur)));
+    }
+
+    fn sample_packages() -> Vec<Package> {
+        let package = |from: &str, to: &str, weight: &str| Package::new(from.to_string(), to.to_string(), weight.parse().unwrap());
+        vec![
+            package("Spain", "Austria", "1.2 kg"),
+            package("US", "Canada", "3 lb").with_dimensions(Dimensions::new(10, 12, 4, LengthUnit::Inch).unwrap()),
+            package("South Korea", "JP", "500 g").with_dimensions(Dimensions::new(400, 300, 200, LengthUnit::Millimetre).unwrap()),
+        ]
+    }
+
+    #[test]
+    fn manifest_round_trips_through_csv_and_json() {
+        let packages = sample_packages();
+        let csv = Manifest::write(&packages, ManifestFormat::Csv);
+        assert_eq!(
+            csv,
+            "sender,recipient,weight,length_mm,width_mm,height_mm\n\
+             ES,AT,1200 g,,,\n\
+             US,CA,1360.77711 g,254,304.8,101.6\n\
+             KR,JP,500 g,400,300,200\n"
+        );
+        let json = Manifest::write(&packages, ManifestFormat::Json);
+        assert!(json.contains(r#"{"sender": "US", "recipient": "CA", "weight": "1360.77711 g", "length_mm": 254, "width_mm": 304.8, "height_mm": 101.6}"#));
+
+        for (text, format) in [(csv, ManifestFormat::Csv), (json, ManifestFormat::Json)] {
+            let manifest = Manifest::read(&text, format).unwrap();
+            assert_eq!(manifest.errors, vec![]);
+            assert_eq!(manifest.packages, packages);
+        }
+        assert_eq!(Manifest::write(&[], ManifestFormat::Json), "[]\n");
+    }
+
+    #[test]
+    fn csv_manifest_reports_bad_rows_with_line_numbers() {
+        let text = "\u{feff}Weight,Sender,Recipient,Length_mm,Width_mm,Height_mm,note\r\n\
+                    1.2 kg,Spain,Austria,,,,ok\r\n\
+                    \r\n\
+                    2 kg,Spain,Atlantis,,,,\r\n\
+                    two kg,Spain,Austria,,,,\r\n\
+                    500 g,\"Korea, Republic of\",ES,10,10,,\r\n\
+                    500 g,\"Korea, Republic of\",ES,10,10.5,0.25,\"says \"\"hi\"\"\"\r\n\
+                    500 g,Spain\r\n\
+                    31 kg,Spain,Austria,,,,\r\n";
+        let manifest = Manifest::read(text, ManifestFormat::Csv).unwrap();
+        assert_eq!(manifest.packages.len(), 2);
+        assert_eq!(manifest.packages[1].dimensions, Some(Dimensions::new(10_000, 10_500, 250, LengthUnit::Micrometre).unwrap()));
+        let errors: Vec<String> = manifest.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "line 4: unknown recipient country \"Atlantis\"",
+                "line 5: invalid weight \"two kg\"",
+                "line 6: missing field height_mm",
+                "line 8: expected 7 fields, found 2",
+                "line 9: package weighs 31000 g, the limit is 30000 g",
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_manifest_needs_a_header() {
+        let error = |text| Manifest::read(text, ManifestFormat::Csv).unwrap_err();
+        assert_eq!(error("\n\nsender,weight\nES,1 kg\n"), RowError { line: 3, error: ManifestError::MissingField("recipient") });
+        assert_eq!(error("").to_string(), "line 1: manifest is empty");
+        assert_eq!(error("sender,\"recipient,weight\n").error, ManifestError::Syntax(String::from("unterminated quoted field")));
+    }
+
+    #[test]
+    fn json_manifest_reports_bad_rows_with_line_numbers() {
+        let text = r#"[
+            {"sender": "Spain", "recipient": "Austria", "weight": "1.2 kg", "note": null},
+            {"sender": "Spain", "recipient": "Austria", "weight": 1200},
+            "ES,AT,1 kg",
+            {"sender": "Spain", "recipient": "Austria", "weight": "1 kg", "tags": ["fragile"]},
+            {
+                "sender": "\u00c5land Islands", "recipient": "FI", "weight": "250 g",
+                "length_mm": 100, "width_mm": 100, "height_mm": -1
+            },
+            {"sender": "\ud83d\ude80", "recipient": "FI", "weight": "250 g", "length_mm": 1, "width_mm": 2, "height_mm": 3}
+        ]"#;
+        let manifest = Manifest::read(text, ManifestFormat::Json).unwrap();
+        assert_eq!(manifest.packages.len(), 1);
+        let errors: Vec<String> = manifest.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "line 3: weight needs a unit (g, kg, lb or oz)",
+                "line 4: expected an object",
+                "line 5: field tags must be a string or a number",
+                "line 6: invalid height_mm \"-1\"",
+                "line 10: unknown sender country \"🚀\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn json_manifest_syntax_errors_stop_the_import() {
+        let error = |text| Manifest::read(text, ManifestFormat::Json).unwrap_err().to_string();
+        assert_eq!(error("{}"), "line 1: expected '['");
+        assert_eq!(error("[\n  {\"sender\": \"ES\",\n  \"weight\" \"1 kg\"}\n]"), "line 3: expected ':'");
+        assert_eq!(error("[{\"sender\": \"ES\"}"), "line 1: expected ',' or ']'");
+        assert_eq!(error("[{\"sender\": \"\\ud83d\"}]"), "line 1: unpaired surrogate");
+        assert_eq!(error("[] []"), "line 1: unexpected text after the manifest");
+        for number in ["1-2e", "01", "1.", "-", "1e", "1e+", "1.2.3", "--1"] {
+            let text = format!("[{{\"weight\": {number}}}]");
+            let error = Manifest::read(&text, ManifestFormat::Json).unwrap_err();
+            assert_eq!(error.to_string(), "line 1: invalid number", "{number}");
+        }
+    }
+
+    #[test]
+    fn json_manifest_limits_nesting_depth() {
+        // 外层的清单数组不算，depth 是包裹记录位置上嵌套的数组层数
+        let nested = |depth: usize| format!("[{}{}]", "[".repeat(depth), "]".repeat(depth));
+        let error = Manifest::read(&nested(MAX_JSON_DEPTH + 1), ManifestFormat::Json).unwrap_err();
+        assert_eq!(error.to_string(), "line 1: nesting too deep");
+        // 刚好到上限的还能解析，只是这条记录不是对象
+        let manifest = Manifest::read(&nested(MAX_JSON_DEPTH), ManifestFormat::Json).unwrap();
+        assert_eq!(manifest.errors.len(), 1);
+        // 远超上限也只是报错，不会栈溢出
+        assert!(Manifest::read(&"[".repeat(1_000_000), ManifestFormat::Json).is_err());
+    }
+
+    fn at(minutes: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_760_000_000 + minutes * 60)
+    }
+
+    #[test]
+    fn tracking_follows_the_delivery_lifecycle() {
+        let package = Package::new(String::from("Spain"), String::from("Austria"), Weight::grams(1_200));
+        let mut tracked = package.track(at(0), "Madrid");
+        assert_eq!(tracked.package.weight, Weight::grams(1_200));
+        assert_eq!(tracked.tracking.state(), TrackingState::Created);
+        assert!(!tracked.tracking.state().is_final());
+
+        let steps = [
+            (TrackingState::LabelPrinted, 5, "Madrid"),
+            (TrackingState::InTransit, 60, "Madrid hub"),
+            (TrackingState::InTransit, 900, "Vienna hub"),
+            (TrackingState::OutForDelivery, 1_000, "Vienna"),
+            // 没人在家，第二天再送
+            (TrackingState::InTransit, 1_200, "Vienna"),
+            (TrackingState::OutForDelivery, 2_400, "Vienna"),
+            (TrackingState::Delivered, 2_400, "Vienna"),
+        ];
+        for (state, minutes, location) in steps {
+            assert_eq!(tracked.tracking.advance(state, at(minutes), location), Ok(()));
+        }
+        assert_eq!(tracked.tracking.events.len(), 8);
+        assert_eq!(tracked.tracking.events[3].location, "Vienna hub");
+        assert_eq!(tracked.tracking.delivered_at(), Some(at(2_400)));
+        assert!(tracked.tracking.state().is_final());
+    }
+
+    #[test]
+    fn tracking_rejects_illegal_transitions() {
+        let mut tracking = Tracking::new(at(0), "Madrid");
+        let error = tracking.advance(TrackingState::Delivered, at(1), "Vienna").unwrap_err();
+        assert_eq!(error, TrackingError::IllegalTransition { from: TrackingState::Created, to: TrackingState::Delivered });
+        assert_eq!(error.to_string(), "package can not go from created to delivered");
+        // 没打面单不能退回
+        assert!(tracking.advance(TrackingState::Returned, at(1), "Madrid").is_err());
+
+        tracking.advance(TrackingState::LabelPrinted, at(1), "Madrid").unwrap();
+        tracking.advance(TrackingState::InTransit, at(2), "Madrid hub").unwrap();
+        tracking.advance(TrackingState::Returned, at(3), "Madrid").unwrap();
+        // 退回之后不能再有事件
+        for next in [TrackingState::InTransit, TrackingState::Delivered, TrackingState::Returned] {
+            assert_eq!(
+                tracking.advance(next, at(4), "Madrid"),
+                Err(TrackingError::IllegalTransition { from: TrackingState::Returned, to: next })
+            );
+        }
+        assert_eq!(tracking.events.len(), 4);
+        assert_eq!(tracking.delivered_at(), None);
+    }
+
+    #[test]
+    fn tracking_rejects_events_out_of_order() {
+        let mut tracking = Tracking::new(at(10), "Madrid");
+        assert_eq!(
+            tracking.advance(TrackingState::LabelPrinted, at(9), "Madrid"),
+            Err(TrackingError::OutOfOrder { previous: at(10), at: at(9) })
+        );
+        assert_eq!(tracking.state(), TrackingState::Created);
+        // 同一时刻的事件可以接受
+        assert_eq!(tracking.advance(TrackingState::LabelPrinted, at(10), "Madrid"), Ok(()));
+    }
+
+    #[test]
+    fn tracking_numbers_carry_an_s10_check_digit() {
+        let gb = Country::parse("GB").unwrap();
+        let number = TrackingNumber::new("RR", 47_312_482, gb).unwrap();
+        assert_eq!(number.to_string(), "RR473124829GB");
+        // 余数为 1 和 0 时的特殊规则
+        assert_eq!(s10_check_digit(8), 0);
+        assert_eq!(s10_check_digit(0), 5);
+        assert_eq!(TrackingNumber::parse(" rr 473 124 829 gb"), Ok(number));
+        for bad in ["RR473124828GB", "RR47312482GB", "RR473124829XX", "R1473124829GB", "RR4731248é9GB", ""] {
+            assert_eq!(TrackingNumber::parse(bad), Err(LabelError::InvalidTrackingNumber(bad.to_string())));
+        }
+        assert_eq!(TrackingNumber::new("rr", 1, gb), Err(LabelError::InvalidService(String::from("rr"))));
+        assert_eq!(TrackingNumber::new("RR", 100_000_000, gb), Err(LabelError::SerialOutOfRange(100_000_000)));
+    }
+
+    #[test]
+    fn code128_table_and_encoding() {
+        // 每个符号 11 个模块、黑条宽度之和为偶数，终止符 13 个模块
+        for pattern in &CODE128_PATTERNS[..CODE128_STOP] {
+            let widths: Vec<u32> = pattern.bytes().map(|b| (b - b'0') as u32).collect();
+            assert_eq!(widths.iter().sum::<u32>(), 11, "{pattern}");
+            assert_eq!((widths[0] + widths[2] + widths[4]) % 2, 0, "{pattern}");
+        }
+        // "PJJ123C"：(104 + 48*1 + 42*2 + 42*3 + 17*4 + 18*5 + 19*6 + 35*7) % 103 = 55
+        let modules = code128("PJJ123C").unwrap();
+        assert_eq!(modules.len(), 11 * 9 + 13);
+        let symbol = |i: usize| -> String {
+            let bits = &modules[i * 11..i * 11 + 11];
+            let mut widths = String::new();
+            let mut j = 0;
+            while j < bits.len() {
+                let run = bits[j..].iter().take_while(|&&b| b == bits[j]).count();
+                widths.push_str(&run.to_string());
+                j += run;
+            }
+            widths
+        };
+        assert_eq!(symbol(0), CODE128_PATTERNS[CODE128_START_B]);
+        assert_eq!(symbol(1), CODE128_PATTERNS[48]);
+        assert_eq!(symbol(8), CODE128_PATTERNS[55]);
+        assert!(modules.ends_with(&[true, true, false, false, false, true, true, true, false, true, false, true, true]));
+        assert_eq!(code128("tab\t"), None);
+    }
+
+    #[test]
+    fn labels_render_as_zpl_and_svg() {
+        let package = Package::new(String::from("Côte d'Ivoire"), String::from("Spain"), Weight::grams(1_200));
+        let fee = Money::new(4_500, Currency::new("EUR").unwrap());
+        let number = TrackingNumber::new("RR", 47_312_482, package.sender_country).unwrap();
+        let label = Label::new(&package, number, fee);
+
+        assert_eq!(
+            label.to_zpl(),
+            "^XA\n^CI28\n\
+             ^FO50,50^A0N,30,30^FDFROM: Côte d'Ivoire (CI)^FS\n\
+             ^FO50,90^A0N,30,30^FDTO: Spain (ES)^FS\n\
+             ^FO50,130^A0N,30,30^FDWEIGHT: 1200 g^FS\n\
+             ^FO50,170^A0N,30,30^FDFEE: 45.00 EUR^FS\n\
+             ^FO50,230^BY2^BCN,120,Y,N,N^FDRR473124829CI^FS\n^XZ\n"
+        );
+
+        let svg = label.to_svg();
+        // 条码 (15 * 11 + 13) * 2 = 356 像素，加上空白区不到最小宽度 400
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"400\""));
+        assert!(svg.contains(">FROM: Côte d&apos;Ivoire (CI)</text>"));
+        assert!(svg.contains(">FEE: 45.00 EUR</text>"));
+        assert!(svg.contains(">RR473124829CI</text>"));
+        // 起始符 B（211214）的第一条黑条 2 个模块宽，从空白区之后开始
+        assert!(svg.contains("<rect x=\"20\" y=\"130\" width=\"4\" height=\"80\"/>"));
+        let bars = svg.matches("height=\"80\"").count();
+        // 每个符号 3 条黑条，终止符 4 条：13 个字符 + 起始符 + 校验符
+        assert_eq!(bars, 15 * 3 + 4);
+    }
+
+    #[test]
+    fn try_new_rejects_bad_weights() {
+        // 语法：Result 可以直接和 Err(...) 比较，因为 Package 和 PackageError 都实现了 PartialEq
+        let package = |weight| Package::try_new(String::from("Spain"), String::from("Austria"), Weight::grams(weight)).map(|p| p.weight);
+        assert_eq!(package(0), Err(PackageError::NonPositiveWeight(Weight::grams(0))));
+        assert_eq!(package(-2210), Err(PackageError::NonPositiveWeight(Weight::grams(-2210))));
+        assert_eq!(package(30_001), Err(PackageError::Overweight(Weight::grams(30_001))));
+        assert_eq!(package(30_000), Ok(MAX_WEIGHT));
+        assert_eq!(PackageError::Overweight(Weight::grams(30_001)).to_string(), "package weighs 30001 g, the limit is 30000 g");
+    }
+
+    #[test]
+    fn try_new_rejects_bad_countries() {
+        let error = |sender: &str, recipient: &str| {
+            Package::try_new(sender.to_string(), recipient.to_string(), Weight::grams(1200)).unwrap_err()
+        };
+        assert_eq!(error("  ", "Austria"), PackageError::EmptyCountry(Party::Sender));
+        assert_eq!(error("Spain", ""), PackageError::EmptyCountry(Party::Recipient));
+        assert_eq!(error("Spain", "Atlantis"), PackageError::UnknownCountry(Party::Recipient, String::from("Atlantis")));
+        assert_eq!(error("Spain", "Atlantis").to_string(), "unknown recipient country \"Atlantis\"");
+    }
+
+    #[test]
+    fn try_new_normalizes_country_names() {
+        let package = Package::try_new(String::from(" spain "), String::from("ES"), Weight::grams(500)).unwrap();
+        assert_eq!(package.sender_country, Country::parse("ESP").unwrap());
+        assert_eq!(package.sender_country.to_string(), "Spain");
+        assert!(!package.is_international());
+    }
+
+    #[test]
+    fn country_parses_codes_and_names() {
+        let korea = Country::parse("KR").unwrap();
+        assert_eq!(Country::parse("kor"), Some(korea));
+        assert_eq!(Country::parse("south korea"), Some(korea));
+        assert_eq!(Country::parse("Korea, Republic of"), Some(korea));
+        assert_eq!((korea.alpha2(), korea.alpha3(), korea.numeric()), ("KR", "KOR", 410));
+        assert_eq!(korea.to_string(), "South Korea");
+        // 官方全称和常用旧名
+        assert_eq!(Country::parse("Kingdom of Spain"), Country::parse("ES"));
+        assert_eq!(Country::parse("Turkey").map(|c| c.alpha2()), Some("TR"));
+        assert_eq!(Country::parse("Åland Islands").map(|c| c.alpha3()), Some("ALA"));
+        assert_eq!(Country::parse("XX"), None);
+        assert_eq!(Country::parse(""), None);
+    }
+
+    #[test]
+    fn country_codes_and_names_are_unique() {
+        // 代码和名称在 parse 里是同一个查找空间，重复的条目只有第一条能被找到
+        let mut keys = std::collections::HashSet::new();
+        let mut numerics = std::collections::HashSet::new();
+        for record in COUNTRIES {
+            assert!(numerics.insert(record.numeric), "numeric code {} appears twice", record.numeric);
+            let names = [record.alpha2, record.alpha3, record.name].into_iter().chain(record.other_names.iter().copied());
+            for name in names {
+                assert!(keys.insert(name.to_lowercase()), "{name:?} ({}) appears twice in COUNTRIES", record.alpha2);
+            }
+        }
     }
 }